@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    slice,
+};
 
 use rusqlite::{types, Connection};
 
@@ -17,7 +20,7 @@ pub fn n1(
     verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
 ) -> anyhow::Result<()> {
     let fk_val = p_val.clone();
-    verify_values_required(&[fk_val.clone()], table_name, p_col_name)?;
+    verify_values_required(slice::from_ref(&fk_val), table_name, p_col_name)?;
     let mut verified_input = get_verified_insert_inputs(table_name, input, verification_options)?;
     verified_input.insert(p_col_name.to_string(), fk_val);
     create::i_one(conn, table_name, &verified_input, None)?;
@@ -41,14 +44,14 @@ pub fn nn(
         None => types::Value::Text("".to_string()),
     };
     verify_values_required(fk_vals, rel_table_name, peer_col_in_rel)?;
-    verify_values_required(&[my_pk_val.clone()], rel_table_name, main_col_in_rel)?;
+    verify_values_required(slice::from_ref(&my_pk_val), rel_table_name, main_col_in_rel)?;
     create::i_one(conn, table_name, input, verification_options)?;
     for fk_val in fk_vals {
         relink::nn(
             conn,
             rel_table_name,
-            (main_col_in_rel, &[my_pk_val.clone()]),
-            (peer_col_in_rel, &[fk_val.clone()]),
+            (main_col_in_rel, slice::from_ref(&my_pk_val)),
+            (peer_col_in_rel, slice::from_ref(fk_val)),
         )?;
     }
     Ok(())
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
 
-use rusqlite::{types, Connection};
+use anyhow::anyhow;
+use rusqlite::{params_from_iter, types, Connection};
 use serde::de::DeserializeOwned;
-use serde_json::from_value;
 
-use crate::crud::{fetch, shift, sql};
+use crate::crud::{fetch, shift, sql, total, verify::verify_table_name};
+
+const RANK_IN_PARENT_COL: &str = "__rank_in_parent";
 
 fn get_peer_matching_clause(
     rel_name: &str,
@@ -19,6 +21,88 @@ fn get_peer_matching_clause(
     )
 }
 
+fn get_n1_q_config(
+    (parent_col, parents): (&str, &[types::Value]),
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> anyhow::Result<(String, Vec<types::Value>)> {
+    let (bond_matching_clause, bond_matching_params) = sql::in_them(parent_col, parents);
+    let bond_match_refs = (
+        bond_matching_clause.as_str(),
+        bond_matching_params.as_slice(),
+    );
+    sql::merge_q_configs(Some(bond_match_refs), where_q_config, "AND")
+}
+
+///
+/// the same as [`get_n1_q_config`], but the parents are split into chunks of at most `chunk_size` keys,
+/// see [`sql::in_them_chunks`]
+fn get_n1_q_configs(
+    (parent_col, parents): (&str, &[types::Value]),
+    where_q_config: Option<(&str, &[types::Value])>,
+    chunk_size: usize,
+) -> anyhow::Result<Vec<(String, Vec<types::Value>)>> {
+    sql::in_them_chunks(parent_col, parents, chunk_size, false)
+        .into_iter()
+        .map(|(bond_matching_clause, bond_matching_params)| {
+            let bond_match_refs = (
+                bond_matching_clause.as_str(),
+                bond_matching_params.as_slice(),
+            );
+            sql::merge_q_configs(Some(bond_match_refs), where_q_config, "AND")
+        })
+        .collect()
+}
+
+///
+/// compare 2 values the way SQLite orders them, i.e. NULL < numbers < text < blobs
+fn cmp_values(a: &types::Value, b: &types::Value) -> Ordering {
+    fn class_of(value: &types::Value) -> u8 {
+        match value {
+            types::Value::Null => 0,
+            types::Value::Integer(_) | types::Value::Real(_) => 1,
+            types::Value::Text(_) => 2,
+            types::Value::Blob(_) => 3,
+        }
+    }
+    match (a, b) {
+        (types::Value::Integer(a), types::Value::Integer(b)) => a.cmp(b),
+        (types::Value::Integer(a), types::Value::Real(b)) => {
+            (*a as f64).partial_cmp(b).unwrap_or(Ordering::Equal)
+        }
+        (types::Value::Real(a), types::Value::Integer(b)) => {
+            a.partial_cmp(&(*b as f64)).unwrap_or(Ordering::Equal)
+        }
+        (types::Value::Real(a), types::Value::Real(b)) => {
+            a.partial_cmp(b).unwrap_or(Ordering::Equal)
+        }
+        (types::Value::Text(a), types::Value::Text(b)) => a.cmp(b),
+        (types::Value::Blob(a), types::Value::Blob(b)) => a.cmp(b),
+        _ => class_of(a).cmp(&class_of(b)),
+    }
+}
+
+fn get_nn_q_config(
+    (main_table, main_pk_name, main_col_in_rel): (&str, &str, &str),
+    (rel_name, rel_peer_col, related_to): (&str, &str, &[types::Value]),
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> anyhow::Result<(String, Vec<types::Value>)> {
+    let (bond_matching_clause, bond_matching_params) = sql::in_them(rel_peer_col, related_to);
+    let bond_matching_clause = get_peer_matching_clause(
+        rel_name,
+        main_col_in_rel,
+        (main_table, main_pk_name),
+        bond_matching_clause.as_str(),
+    );
+    sql::merge_q_configs(
+        Some((
+            bond_matching_clause.as_str(),
+            bond_matching_params.as_slice(),
+        )),
+        where_q_config,
+        "AND",
+    )
+}
+
 ///
 /// fetch all matching records from the child table
 /// where the parent record is in the given list
@@ -27,31 +111,97 @@ fn get_peer_matching_clause(
 /// * `conn` - the Rusqlite connection to the database
 /// * `child_table_name` - the name of the child table (n in n-1)
 /// * `parent_config` - the parent-related table matching settings (1 in n-1)
-///                     - `tuple(column_name_of_parent_in_child_node_table, key_values_of_the_parent_nodes)`
-///                       (it can return results that belong to multiple parent nodes)
+///   - `tuple(column_name_of_parent_in_child_node_table, key_values_of_the_parent_nodes)`
+///     (it can return results that belong to multiple parent nodes)
 /// * `display_fields` - the fields to be displayed in the result
 /// * `where_q_config` - the where clause and the parameters for condition matching
+///
+/// A large list of parent keys is fetched in chunks, see [`fetch::f_by_pk`]
 pub fn list_n_of_1(
     conn: &Connection,
     child_table_name: &str,
+    (parent_col, parents): (&str, &[types::Value]),
+    display_fields: Option<&[&str]>,
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
+    let chunk_size = fetch::fit_pk_chunk_size(where_q_config);
+    let mut result = vec![];
+    for (where_clause, where_params) in
+        get_n1_q_configs((parent_col, parents), where_q_config, chunk_size)?
+    {
+        result.extend(fetch::f_all(
+            conn,
+            child_table_name,
+            Some((where_clause.as_str(), &where_params)),
+            (false, display_fields),
+        )?);
+    }
+    Ok(result)
+}
+
+///
+/// fetch all matching records from the child table together with the fields of their parent records,
+/// the parent fields are prefixed with the parent table name, i.e. `<parent_table>__<field>`,
+/// so that they never collide with the fields of the child table
+/// # Arguments
+///
+/// * `conn` - the Rusqlite connection to the database
+/// * `child_table_name` - the name of the child table (n in n-1)
+/// * `parent_info_config` - the parent table settings
+///   - `tuple(parent_table_name, parent_table_primary_key_column_name, parent_fields_to_embed)`
+/// * `parent_config` - the parent-related table matching settings (1 in n-1),
+///   see `parent_config` of [`list_n_of_1`]
+/// * `display_fields` - the fields of the child table to be displayed in the result
+/// * `where_q_config` - the where clause and the parameters for condition matching,
+///   it only applies to the child table
+///
+/// A large list of parent keys is fetched in chunks, see [`list_n_of_1`]
+pub fn list_n_of_1_with_parent(
+    conn: &Connection,
+    child_table_name: &str,
+    (parent_table_name, parent_pk_name, parent_fields): (&str, &str, &[&str]),
     parent_config: (&str, &[types::Value]),
     display_fields: Option<&[&str]>,
     where_q_config: Option<(&str, &[types::Value])>,
 ) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
-    let (parent_col, parents) = parent_config;
-    let (bond_matching_clause, bond_matching_params) = sql::in_them(parent_col, parents);
-    let bond_match_refs = (
-        bond_matching_clause.as_str(),
-        bond_matching_params.as_slice(),
-    );
-    let (where_clause, where_params) =
-        sql::merge_q_configs(Some(bond_match_refs), where_q_config, "AND")?;
-    let result = fetch::f_all(
-        conn,
-        child_table_name,
-        Some((where_clause.as_str(), &where_params)),
-        (false, display_fields),
-    )?;
+    verify_table_name(child_table_name)?;
+    verify_table_name(parent_table_name)?;
+    let (parent_col, _) = parent_config;
+    let mut select_fields = match display_fields {
+        Some(fields) => fields
+            .iter()
+            .map(|field| format!("{}.{}", child_table_name, field))
+            .collect::<Vec<_>>(),
+        None => vec![format!("{}.*", child_table_name)],
+    };
+    select_fields.extend(parent_fields.iter().map(|field| {
+        format!(
+            "{}.{} AS {}__{}",
+            parent_table_name, field, parent_table_name, field
+        )
+    }));
+    let chunk_size = fetch::fit_pk_chunk_size(where_q_config);
+    let mut result = Vec::new();
+    for (where_clause, where_params) in get_n1_q_configs(parent_config, where_q_config, chunk_size)?
+    {
+        let sql = format!(
+            "SELECT {} FROM (SELECT * FROM {} WHERE {}) AS {} LEFT JOIN {} ON {}.{} = {}.{}",
+            select_fields.join(", "),
+            child_table_name,
+            where_clause,
+            child_table_name,
+            parent_table_name,
+            child_table_name,
+            parent_col,
+            parent_table_name,
+            parent_pk_name,
+        );
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let mut rows = stmt.query(params_from_iter(&where_params))?;
+        while let Some(row) = rows.next()? {
+            result.push(shift::row_to_map(row)?);
+        }
+    }
     Ok(result)
 }
 
@@ -63,11 +213,205 @@ pub fn list_n_of_1_as<T: DeserializeOwned>(
     where_conf: Option<(&str, &[types::Value])>,
 ) -> anyhow::Result<Vec<T>> {
     let result = list_n_of_1(conn, child_table, parent_config, d_fields, where_conf)?;
-    let mut result_as = Vec::new();
-    for row in result {
-        result_as.push(from_value(shift::val_to_json(&row)?)?);
+    shift::list_as(child_table, &result)
+}
+
+///
+/// count all matching records from the child table
+/// where the parent record is in the given list
+/// # Arguments
+///
+/// * `conn` - the Rusqlite connection to the database
+/// * `child_table_name` - the name of the child table (n in n-1)
+/// * `parent_config` - the parent-related table matching settings (1 in n-1),
+///   see `parent_config` of [`list_n_of_1`]
+/// * `distinct_field` - if provided, count distinct values in this field
+/// * `where_q_config` - the where clause and the parameters for condition matching
+///
+/// Unlike [`list_n_of_1`], the parent keys are counted by a single statement,
+/// so the parent keys and the parameters of `where_q_config` together cannot exceed [`sql::MAX_BOUND_PARAMS`]
+pub fn count_n_of_1(
+    conn: &Connection,
+    child_table_name: &str,
+    parent_config: (&str, &[types::Value]),
+    distinct_field: Option<&str>,
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> anyhow::Result<i64> {
+    let (where_clause, where_params) = get_n1_q_config(parent_config, where_q_config)?;
+    total::t_all(
+        conn,
+        child_table_name,
+        distinct_field,
+        Some((where_clause.as_str(), &where_params)),
+    )
+}
+
+///
+/// find the child records whose parent column points at a parent record that doesn't exist,
+/// child records without a parent (NULL in the parent column) are not considered orphans
+/// # Arguments
+///
+/// * `conn` - the Rusqlite connection to the database
+/// * `child_info_config` - the child table settings (n in n-1)
+///   - `tuple(child_table_name, child_table_primary_key_column_name, column_name_of_parent_in_child_node_table)`
+/// * `parent_info_config` - the parent table settings (1 in n-1)
+///   - `tuple(parent_table_name, parent_table_primary_key_column_name)`
+/// # Returns
+/// * the primary key values of the orphaned child records, in ascending order
+pub fn list_orphans_n_of_1(
+    conn: &Connection,
+    (child_table_name, child_pk_name, parent_col): (&str, &str, &str),
+    (parent_table_name, parent_pk_name): (&str, &str),
+) -> anyhow::Result<Vec<types::Value>> {
+    verify_table_name(child_table_name)?;
+    verify_table_name(parent_table_name)?;
+    let sql = format!(
+        "SELECT kid.{} FROM {} AS kid LEFT JOIN {} AS parent ON kid.{} = parent.{} WHERE kid.{} IS NOT NULL AND parent.{} IS NULL ORDER BY kid.{}",
+        child_pk_name,
+        child_table_name,
+        parent_table_name,
+        parent_col,
+        parent_pk_name,
+        parent_col,
+        parent_pk_name,
+        child_pk_name,
+    );
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let mut rows = stmt.query([])?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        result.push(row.get(0)?);
+    }
+    Ok(result)
+}
+
+///
+/// fetch all matching records from the parent table, each with the number of its child records
+/// in an extra field named `<child_table_name>_count`, e.g. `song_count`.
+/// The counting is done in the same query, parents without children are counted as 0
+/// # Arguments
+///
+/// * `conn` - the Rusqlite connection to the database
+/// * `parent_info_config` - `tuple(parent_table_name, parent_table_primary_key_column_name)`
+/// * `child_info_config` - `tuple(child_table_name, column_name_of_the_parent_in_child_table)`
+/// * `display_fields` - the fields of the parent table to be displayed in the result
+/// * `where_q_config` - the where clause and the parameters for condition matching,
+///   it only applies to the parent table
+pub fn list_1_with_n_count(
+    conn: &Connection,
+    (parent_table_name, parent_pk_name): (&str, &str),
+    (child_table_name, parent_col): (&str, &str),
+    display_fields: Option<&[&str]>,
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
+    verify_table_name(parent_table_name)?;
+    verify_table_name(child_table_name)?;
+    let display_fields = match display_fields {
+        Some(fields) => fields
+            .iter()
+            .map(|field| format!("{}.{}", parent_table_name, field))
+            .collect::<Vec<_>>()
+            .join(", "),
+        None => format!("{}.*", parent_table_name),
+    };
+    let (where_clause, where_params) = sql::standardize_q_config(where_q_config, "WHERE")?;
+    let sql = format!(
+        "SELECT {}, (SELECT COUNT(*) FROM {} WHERE {}.{} = {}.{}) AS {}_count FROM {} {}",
+        display_fields,
+        child_table_name,
+        child_table_name,
+        parent_col,
+        parent_table_name,
+        parent_pk_name,
+        child_table_name,
+        parent_table_name,
+        where_clause,
+    );
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let mut rows = stmt.query(params_from_iter(&where_params))?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        result.push(shift::row_to_map(row)?);
+    }
+    Ok(result)
+}
+
+///
+/// fetch the first few matching records of each parent record from the child table,
+/// the limit and the ordering are applied within each parent rather than the whole result
+/// # Arguments
+///
+/// * `conn` - the Rusqlite connection to the database
+/// * `child_table_name` - the name of the child table (n in n-1)
+/// * `parent_config` - the parent-related table matching settings (1 in n-1),
+///   see `parent_config` of [`list_n_of_1`]
+/// * `top_config` - `tuple(order_column, is_descending, max_records_per_parent)`
+/// * `display_fields` - the fields to be displayed in the result
+/// * `where_q_config` - the where clause and the parameters for condition matching
+/// # Returns
+/// * the records are grouped by the parent column, and ordered within each parent
+///
+/// A large list of parent keys is fetched in chunks, see [`list_n_of_1`],
+/// the order of the result is the same as fetching them at once
+pub fn list_top_n_of_1(
+    conn: &Connection,
+    child_table_name: &str,
+    parent_config: (&str, &[types::Value]),
+    (order_col, is_desc, per_parent): (&str, bool, u32),
+    display_fields: Option<&[&str]>,
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
+    verify_table_name(child_table_name)?;
+    if per_parent == 0 {
+        return Err(anyhow!(
+            "(table: {}) At least 1 record per parent is required for this operation",
+            child_table_name
+        ));
+    }
+    let (parent_col, parents) = parent_config;
+    let display_fields = match display_fields {
+        Some(fields) => fields.join(", "),
+        None => "*".to_string(),
+    };
+    let order_word = if is_desc { "DESC" } else { "ASC" };
+    // the parents are sorted so that the chunks follow each other in the order of the parent column,
+    // the ranked records of all chunks can then be merged by a stable sort of their ranks
+    let mut parents = parents.to_vec();
+    parents.sort_by(cmp_values);
+    let other_param_count = where_q_config.map_or(0, |(_, params)| params.len()) + 1;
+    let chunk_size = sql::fit_chunk_size(fetch::PK_CHUNK_SIZE, other_param_count);
+    let mut ranked = Vec::new();
+    for (where_clause, where_params) in
+        get_n1_q_configs((parent_col, &parents), where_q_config, chunk_size)?
+    {
+        let sql = format!(
+            "SELECT {}, {} FROM (SELECT *, ROW_NUMBER() OVER (PARTITION BY {} ORDER BY {} {}) AS {} FROM {} WHERE {}) WHERE {} <= ? ORDER BY {}, {}",
+            display_fields,
+            RANK_IN_PARENT_COL,
+            parent_col,
+            order_col,
+            order_word,
+            RANK_IN_PARENT_COL,
+            child_table_name,
+            where_clause,
+            RANK_IN_PARENT_COL,
+            parent_col,
+            RANK_IN_PARENT_COL,
+        );
+        let params = [where_params, vec![types::Value::Integer(per_parent as i64)]].concat();
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let mut rows = stmt.query(params_from_iter(&params))?;
+        while let Some(row) = rows.next()? {
+            let mut record = shift::row_to_map(row)?;
+            let rank = match record.remove(RANK_IN_PARENT_COL) {
+                Some(types::Value::Integer(rank)) => rank,
+                _ => 0,
+            };
+            ranked.push((rank, record));
+        }
     }
-    Ok(result_as)
+    ranked.sort_by_key(|(rank, _)| *rank);
+    Ok(ranked.into_iter().map(|(_, record)| record).collect())
 }
 
 ///
@@ -78,10 +422,10 @@ pub fn list_n_of_1_as<T: DeserializeOwned>(
 /// # Arguments
 /// * `conn` - the Rusqlite connection to the database
 /// * `main_info_config` - the main table matching settings
-///                        (the table that will contain the related details together with keys)
-///                      - `tuple(main_table_name, main_table_primary_key_column_name, column_name_in_rel_table)`
+///   (the table that will contain the related details together with keys)
+///   - `tuple(main_table_name, main_table_primary_key_column_name, column_name_in_rel_table)`
 /// * `rel_config` - the relationship table matching settings
-///                  - `tuple(rel_table_name, rel_table_column_name_of_the_related_peer, key_values_of_the_related_peers)`
+///   - `tuple(rel_table_name, rel_table_column_name_of_the_related_peer, key_values_of_the_related_peers)`
 /// * `display_fields` - the fields to be displayed in the result
 /// * `where_q_config` - the where clause and the parameters for condition matching
 pub fn list_n_of_n(
@@ -91,26 +435,9 @@ pub fn list_n_of_n(
     display_fields: Option<&[&str]>,
     where_q_config: Option<(&str, &[types::Value])>,
 ) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
-    let (main_table, main_pk_name, main_col_in_rel) = main_info_config;
-    let (rel_name, rel_peer_col, related_to) = rel_config;
-    let (bond_matching_clause, bond_matching_params) = sql::in_them(rel_peer_col, related_to);
-    let (bond_matching_clause, bond_matching_params) = (
-        get_peer_matching_clause(
-            rel_name,
-            main_col_in_rel,
-            (main_table, main_pk_name),
-            bond_matching_clause.as_str(),
-        ),
-        bond_matching_params,
-    );
-    let (where_clause, where_params) = sql::merge_q_configs(
-        Some((
-            bond_matching_clause.as_str(),
-            bond_matching_params.as_slice(),
-        )),
-        where_q_config,
-        "AND",
-    )?;
+    let (main_table, _, _) = main_info_config;
+    let (where_clause, where_params) =
+        get_nn_q_config(main_info_config, rel_config, where_q_config)?;
     let result = fetch::f_all(
         conn,
         main_table,
@@ -120,6 +447,34 @@ pub fn list_n_of_n(
     Ok(result)
 }
 
+///
+/// count all matching records from the main table
+/// where the related records are in the given list.
+/// See also [`list_n_of_n`]
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `main_info_config` - see `main_info_config` of [`list_n_of_n`]
+/// * `rel_config` - see `rel_config` of [`list_n_of_n`]
+/// * `distinct_field` - if provided, count distinct values in this field
+/// * `where_q_config` - the where clause and the parameters for condition matching
+pub fn count_n_of_n(
+    conn: &Connection,
+    main_info_config: (&str, &str, &str),
+    rel_config: (&str, &str, &[types::Value]),
+    distinct_field: Option<&str>,
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> anyhow::Result<i64> {
+    let (main_table, _, _) = main_info_config;
+    let (where_clause, where_params) =
+        get_nn_q_config(main_info_config, rel_config, where_q_config)?;
+    total::t_all(
+        conn,
+        main_table,
+        distinct_field,
+        Some((where_clause.as_str(), &where_params)),
+    )
+}
+
 pub fn list_n_of_n_as<T: DeserializeOwned>(
     conn: &Connection,
     info_config: (&str, &str, &str),
@@ -128,9 +483,5 @@ pub fn list_n_of_n_as<T: DeserializeOwned>(
     where_conf: Option<(&str, &[types::Value])>,
 ) -> anyhow::Result<Vec<T>> {
     let result = list_n_of_n(conn, info_config, rel_config, d_fields, where_conf)?;
-    let mut result_as = Vec::new();
-    for row in result {
-        result_as.push(from_value(shift::val_to_json(&row)?)?);
-    }
-    Ok(result_as)
+    shift::list_as(info_config.0, &result)
 }
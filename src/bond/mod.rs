@@ -1,4 +1,4 @@
 pub mod create;
 pub mod fetch;
 pub mod relink;
-pub mod wrap;
\ No newline at end of file
+pub mod wrap;
@@ -1,8 +1,14 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, slice};
 
-use rusqlite::{types, Connection};
+use rusqlite::{params_from_iter, types, Connection};
 
-use crate::crud::{del, sql::merge_q_configs, total, update, verify::verify_values_required};
+use crate::crud::{
+    atomic::in_savepoint,
+    del, fetch,
+    sql::{self, merge_q_configs},
+    update,
+    verify::{verify_table_name, verify_values_required},
+};
 
 ///
 /// build or rebuild the links of the target records to their parent record
@@ -10,7 +16,7 @@ use crate::crud::{del, sql::merge_q_configs, total, update, verify::verify_value
 /// * `conn` - the Rusqlite connection to the database
 /// * `child_table` - the name of the target records' table (child table, n in n-1)
 /// * `parent_config` - the parent-related table matching settings (1 in n-1)
-///                    - `tuple(column_name_of_the_parent_table_in_the_child_table, value_of_the_parent_table_primary_key)`
+///   - `tuple(column_name_of_the_parent_table_in_the_child_table, value_of_the_parent_table_primary_key)`
 /// * `child_config` - the column information of the child table, similar to `parent_config`
 /// * `where_q_config` - the where clause and the parameters for condition matching
 ///
@@ -24,7 +30,7 @@ pub fn n1_by_pk(
     let (parent_col, parent_val) = parent_config;
     let (child_pk_col, child_pk_vals) = child_config;
     let pr_val = parent_val.clone();
-    verify_values_required(&[pr_val.clone()], child_table, parent_col)?;
+    verify_values_required(slice::from_ref(&pr_val), child_table, parent_col)?;
     verify_values_required(child_pk_vals, child_table, child_pk_col)?;
     let input = HashMap::from([(parent_col.to_string(), pr_val)]);
     update::u_by_pk(
@@ -52,9 +58,9 @@ pub fn n1_by_pk(
 /// * `conn` - the Rusqlite connection to the database
 /// * `child_table` - the name of the target records' table (child table, n in n-1)
 /// * `parent_config` - the parent-related table matching settings (1 in n-1)
-///                     - `tuple(column_name_of_parent_in_child_node_table, old_key_value_of_the_parent_node, new_key_value_of_the_parent_node)`
-///                     - <b>WARNING:</b> There might be multiple children with the same parent node,
-///                       be careful about what this value should be
+///   - `tuple(column_name_of_parent_in_child_node_table, old_key_value_of_the_parent_node, new_key_value_of_the_parent_node)`
+///   - <b>WARNING:</b> There might be multiple children with the same parent node,
+///     be careful about what this value should be
 /// * `where_q_config` - the where clause and the parameters for condition matching
 pub fn n1_by_ofk(
     conn: &Connection,
@@ -66,11 +72,14 @@ pub fn n1_by_ofk(
     if parent_old_val == parent_new_val {
         return Ok(());
     }
-    verify_values_required(&[parent_new_val.clone()], child_table, parent_col)?;
-    verify_values_required(&[parent_old_val.clone()], child_table, parent_col)?;
+    verify_values_required(slice::from_ref(parent_new_val), child_table, parent_col)?;
+    verify_values_required(slice::from_ref(parent_old_val), child_table, parent_col)?;
     let input = HashMap::from([(parent_col.to_string(), parent_new_val.clone())]);
     let (where_clause, where_params) = merge_q_configs(
-        Some((&format!("{} = ?", parent_col), &[parent_old_val.clone()])),
+        Some((
+            &format!("{} = ?", parent_col),
+            slice::from_ref(parent_old_val),
+        )),
         where_q_config,
         "AND",
     )?;
@@ -85,43 +94,67 @@ pub fn n1_by_ofk(
 }
 
 ///
-/// check if the link between the target record and the peer record exists
-fn nn_link_exists(
+/// move all the links of a record to another record on the same side of an n-n relationship,
+/// e.g. when merging 2 records into 1.
+/// The links that the new record already has are not duplicated, they are removed from the old record instead,
+/// so the old record has no links afterward
+///
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `rel_name` - the name of the table that represents the n-n relationship
+/// * `side_config` - the moving side of the relationship
+///   - `tuple(column name, old_key_value, new_key_value)`
+/// * `peer_col` - the column name of the other side of the relationship
+/// # Returns
+/// * `Ok(moved_links)` - the number of links that are moved to the new record
+pub fn nn_by_ofk(
     conn: &Connection,
     rel_name: &str,
-    a_config: (&str, &types::Value),
-    b_config: (&str, &types::Value),
-    where_q_config: Option<(&str, &[types::Value])>,
-) -> anyhow::Result<bool> {
-    let (a_col, a_val) = a_config;
-    let (b_col, b_val) = b_config;
-    verify_values_required(&[a_val.clone()], rel_name, a_col)?;
-    verify_values_required(&[b_val.clone()], rel_name, b_col)?;
-    let (where_clause, where_params) = merge_q_configs(
-        Some((
-            format!("{} = ? AND {} = ?", a_col, b_col).as_str(),
-            &[a_val.clone(), b_val.clone()],
-        )),
-        where_q_config,
-        "AND",
-    )?;
-    let count = total::t_all(
-        conn,
-        rel_name,
-        None,
-        Some((where_clause.as_str(), where_params.as_slice())),
-    )?;
-    Ok(count > 0)
+    (col, old_val, new_val): (&str, &types::Value, &types::Value),
+    peer_col: &str,
+) -> anyhow::Result<usize> {
+    verify_table_name(rel_name)?;
+    verify_values_required(slice::from_ref(old_val), rel_name, col)?;
+    verify_values_required(slice::from_ref(new_val), rel_name, col)?;
+    if old_val == new_val {
+        return Ok(0);
+    }
+    in_savepoint(conn, "nn_by_ofk", || {
+        let sql = format!(
+            "UPDATE {} SET {} = ? WHERE {} = ? AND NOT EXISTS (SELECT 1 FROM {} AS dup WHERE dup.{} = ? AND dup.{} = {}.{})",
+            rel_name, col, col, rel_name, col, peer_col, rel_name, peer_col
+        );
+        let moved_links = conn.execute(&sql, [new_val, old_val, new_val])?;
+        del::d_all(
+            conn,
+            rel_name,
+            (&format!("{} = ?", col), slice::from_ref(old_val)),
+        )?;
+        Ok(moved_links)
+    })
 }
 
 ///
-/// build or rebuild the links of the target records to their peers
+/// the maximum number of links inserted by a single statement in [`nn`],
+/// each link takes 2 bound parameters
+const NN_LINKS_PER_INSERT: usize = 400;
+
+///
+/// build or rebuild the links of the target records to their peers,
+/// the links that already exist are skipped.
+/// The missing links are found and inserted by SQLite in batches rather than checked pair by pair,
+/// so the number of statements doesn't grow with the Cartesian product of the records,
+/// see the ignored `bench_link_nn` test.
+///
+/// Note: the repeated values of each side are removed by [`sql::dedup_values`] wherever they are,
+/// earlier versions only skipped repeated values that were next to each other,
+/// so e.g. `[1, 2, 1]` used to insert the links of `1` twice, and now inserts them once
 ///
 /// # Arguments
 /// * `conn` - the Rusqlite connection to the database
 /// * `rel_name` - the name of the table that represents the n-n relationship
 /// * `a_config` - the table matching settings of the A side of the relationship
-///               - `tuple(column name, values_of_the_primary_key_values_of_the_records_in_this_column)`
+///   - `tuple(column name, values_of_the_primary_key_values_of_the_records_in_this_column)`
 /// * `b_config` - the table matching settings of the B side of the relationship, similar to `a_config`
 pub fn nn(
     conn: &Connection,
@@ -131,32 +164,31 @@ pub fn nn(
 ) -> anyhow::Result<()> {
     let (a_col, a_vals) = a_config;
     let (b_col, b_vals) = b_config;
+    verify_table_name(rel_name)?;
     verify_values_required(a_vals, rel_name, a_col)?;
     verify_values_required(b_vals, rel_name, b_col)?;
-    let mut deduped_a_vals = a_vals.to_vec();
-    deduped_a_vals.dedup();
-
-    let mut deduped_b_vals = b_vals.to_vec();
-    deduped_b_vals.dedup();
+    let deduped_a_vals = sql::dedup_values(a_vals);
+    let deduped_b_vals = sql::dedup_values(b_vals);
 
-    let mut pairs_to_insert = vec![];
+    let mut pairs = vec![];
     for a_val in &deduped_a_vals {
         for b_val in &deduped_b_vals {
-            let existed = nn_link_exists(conn, rel_name, (a_col, a_val), (b_col, b_val), None)?;
-            if !existed {
-                pairs_to_insert.push((a_val, b_val));
-            }
+            pairs.push([a_val.clone(), b_val.clone()]);
         }
     }
-    for (a_val, b_val) in pairs_to_insert {
-        let input = HashMap::from([
-            (a_col.to_string(), a_val.clone()),
-            (b_col.to_string(), b_val.clone()),
-        ]);
-        crate::crud::create::i_one(conn, rel_name, &input, None)?;
-    }
-
-    Ok(())
+    in_savepoint(conn, "nn", || {
+        for pair_chunk in pairs.chunks(NN_LINKS_PER_INSERT) {
+            let value_rows = vec!["(?, ?)"; pair_chunk.len()].join(", ");
+            // comparing with the columns of the relationship table applies their type affinity,
+            // so e.g. a text key '1' matches an existing integer key 1
+            let sql = format!(
+                "INSERT INTO {} ({}, {}) SELECT link.column1, link.column2 FROM (VALUES {}) AS link WHERE NOT EXISTS (SELECT 1 FROM {} WHERE {} = link.column1 AND {} = link.column2)",
+                rel_name, a_col, b_col, value_rows, rel_name, a_col, b_col
+            );
+            conn.execute(&sql, params_from_iter(pair_chunk.iter().flatten()))?;
+        }
+        Ok(())
+    })
 }
 
 ///
@@ -166,7 +198,7 @@ pub fn nn(
 /// * `conn` - the Rusqlite connection to the database
 /// * `rel_name` - the name of the table that represents the n-n relationship
 /// * `a_config` - the table matching settings of the A side of the relationship
-///                - `tuple(column name, values_of_the_primary_key_values_of_the_records_in_this_column)`
+///   - `tuple(column name, values_of_the_primary_key_values_of_the_records_in_this_column)`
 /// * `b_config` - the table matching settings of the B side of the relationship, similar to `a_config`
 ///
 pub fn d_all(
@@ -199,3 +231,29 @@ pub fn d_all(
     }
     Ok(())
 }
+
+///
+/// delete all the links of the target records to any of their peers,
+/// the target records and the peer records themselves are left untouched
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `rel_name` - the name of the table that represents the n-n relationship
+/// * `side_config` - the table matching settings of one side of the relationship
+///   - `tuple(column name, values_of_the_primary_key_values_of_the_records_in_this_column)`
+///
+/// A large list of primary key values is unlinked in chunks within a savepoint, see [`del::d_by_pk`]
+pub fn d_all_of(
+    conn: &Connection,
+    rel_name: &str,
+    side_config: (&str, &[types::Value]),
+) -> anyhow::Result<()> {
+    let (col, vals) = side_config;
+    verify_values_required(vals, rel_name, col)?;
+    let link_chunks = sql::in_them_chunks(col, vals, fetch::PK_CHUNK_SIZE, false);
+    in_savepoint(conn, "d_all_of", || {
+        for (link_clause, link_params) in &link_chunks {
+            del::d_all(conn, rel_name, (link_clause.as_str(), link_params))?;
+        }
+        Ok(())
+    })
+}
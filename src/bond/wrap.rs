@@ -1,6 +1,7 @@
 use super::create;
 use crate::TblRep;
 
+use anyhow::anyhow;
 use rusqlite::{types, Connection};
 use serde::de::DeserializeOwned;
 
@@ -94,6 +95,136 @@ impl<'a> N1Wrap<'a> {
         super::fetch::list_n_of_1(conn, table_name, parent_config, d_fields, where_q_config)
     }
 
+    /// List all child records of the parent records together with all the fields of their parent records,
+    /// the parent fields are prefixed with the parent table name, e.g. `artist__name`.
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `parents` - the values of the parent table's primary key
+    /// * `d_fields` - the fields of the child table to be displayed in the result
+    /// * `where_q_config` - the where clause and the parameters for condition matching of the child table
+    pub fn list_kids_with_parent(
+        &self,
+        conn: &Connection,
+        parents: &[types::Value],
+        d_fields: Option<&[&str]>,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
+        let t1 = self.get_t1();
+        let mut parent_fields = t1
+            .get_defaults()
+            .keys()
+            .map(|field| field.as_str())
+            .collect::<Vec<_>>();
+        parent_fields.sort();
+        let parent_info_config = (t1.get_name(), t1.get_pk_name(), parent_fields.as_slice());
+        let parent_config = (self.get_parent_col(), parents);
+        super::fetch::list_n_of_1_with_parent(
+            conn,
+            self.get_tn().get_name(),
+            parent_info_config,
+            parent_config,
+            d_fields,
+            where_q_config,
+        )
+    }
+
+    /// Count all child records of the parent records.
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `parents` - the values of the parent table's primary key
+    /// * `distinct_field` - if provided, count distinct values in this field
+    /// * `where_q_config` - the where clause and the parameters for condition matching
+    pub fn count_kids(
+        &self,
+        conn: &Connection,
+        parents: &[types::Value],
+        distinct_field: Option<&str>,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> anyhow::Result<i64> {
+        let parent_config = (self.get_parent_col(), parents);
+        let table_name = self.get_tn().get_name();
+        super::fetch::count_n_of_1(
+            conn,
+            table_name,
+            parent_config,
+            distinct_field,
+            where_q_config,
+        )
+    }
+
+    /// List the parent records, each with the number of its child records in the field `<child_table_name>_count`.
+    /// See also [`super::fetch::list_1_with_n_count`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `d_fields` - the fields of the parent table to be displayed in the result
+    /// * `where_q_config` - the where clause and the parameters for condition matching of the parent table
+    pub fn list_parents_with_kid_count(
+        &self,
+        conn: &Connection,
+        d_fields: Option<&[&str]>,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
+        let t1 = self.get_t1();
+        super::fetch::list_1_with_n_count(
+            conn,
+            (t1.get_name(), t1.get_pk_name()),
+            (self.get_tn().get_name(), self.get_parent_col()),
+            d_fields,
+            where_q_config,
+        )
+    }
+
+    /// List the primary key values of the child records whose parent records don't exist.
+    /// See also [`super::fetch::list_orphans_n_of_1`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    pub fn list_orphan_kids(&self, conn: &Connection) -> anyhow::Result<Vec<types::Value>> {
+        let tn = self.get_tn();
+        let t1 = self.get_t1();
+        super::fetch::list_orphans_n_of_1(
+            conn,
+            (tn.get_name(), tn.get_pk_name(), self.get_parent_col()),
+            (t1.get_name(), t1.get_pk_name()),
+        )
+    }
+
+    /// List the first few child records of each parent record,
+    /// ordered by a column of the child table within each parent.
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `parents` - the values of the parent table's primary key
+    /// * `top_config` - `tuple(order_column, is_descending, max_records_per_parent)`,
+    ///   the order column must be defined in the child table's [`TblRep`]
+    /// * `d_fields` - the fields to be displayed in the result
+    /// * `where_q_config` - the where clause and the parameters for condition matching
+    pub fn list_top_kids(
+        &self,
+        conn: &Connection,
+        parents: &[types::Value],
+        top_config: (&str, bool, u32),
+        d_fields: Option<&[&str]>,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
+        let (order_col, _, _) = top_config;
+        let table_name = self.get_tn().get_name();
+        if !self.get_tn().get_defaults().contains_key(order_col) {
+            return Err(anyhow!(
+                "(table: {}) The column '{}' cannot be used for ordering",
+                table_name,
+                order_col
+            ));
+        }
+        let parent_config = (self.get_parent_col(), parents);
+        super::fetch::list_top_n_of_1(
+            conn,
+            table_name,
+            parent_config,
+            top_config,
+            d_fields,
+            where_q_config,
+        )
+    }
+
     /// Similar to [`N1Wrap::list_kids`], but returns the result as a vector of the given type.
     /// # Arguments
     /// * `conn` - the Rusqlite connection to the database
@@ -137,10 +268,13 @@ impl<'a> NnWrap<'a> {
     /// * `t1` - the [`TblRep`] of the first table
     /// * `t2` - the [`TblRep`] of the second table
     /// * `rel` - the relationship between the two tables
-    ///          - `tuple(rel_name, t1_col, t2_col)`
-    ///          - `rel_name` is the name of the table that represents the n-n relationship
-    ///          - `t1_col` is the column name of the first table in the relationship table
-    ///          - `t2_col` is the column name of the second table in the relationship table
+    ///   - `tuple(rel_name, t1_col, t2_col)`
+    ///   - `rel_name` is the name of the table that represents the n-n relationship
+    ///   - `t1_col` is the column name of the first table in the relationship table
+    ///   - `t2_col` is the column name of the second table in the relationship table
+    ///
+    /// `t1` and `t2` can be the same table for a self-referential relationship,
+    /// e.g. `rel_user_user(user_id, friend_id)`, the sides are then told apart by `t1_col` and `t2_col`
     pub fn new(t1: &'a TblRep, t2: &'a TblRep, rel: (&'a str, &'a str, &'a str)) -> Self {
         Self {
             t1,
@@ -196,19 +330,90 @@ impl<'a> NnWrap<'a> {
         super::relink::d_all(conn, rel_name, (a_col, a_vals), (b_col, b_vals))
     }
 
+    /// Remove all the links of the given records in the first table to any records of the second table.
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `a_vals` - the values of the first table's primary key
+    pub fn unlink_all_t1(&self, conn: &Connection, a_vals: &[types::Value]) -> anyhow::Result<()> {
+        let (rel_name, a_col, _) = self.get_rel();
+        super::relink::d_all_of(conn, rel_name, (a_col, a_vals))
+    }
+
+    /// Similar to [`NnWrap::unlink_all_t1`], but for the second table.
+    pub fn unlink_all_t2(&self, conn: &Connection, b_vals: &[types::Value]) -> anyhow::Result<()> {
+        let (rel_name, _, b_col) = self.get_rel();
+        super::relink::d_all_of(conn, rel_name, (b_col, b_vals))
+    }
+
+    fn relink_peers(
+        &self,
+        conn: &Connection,
+        is_t1: bool,
+        (old_val, new_val): (&types::Value, &types::Value),
+    ) -> anyhow::Result<usize> {
+        let (rep, col, peer_col) = self.side(is_t1);
+        if !rep.exists_by_pk(conn, std::slice::from_ref(new_val), None)? {
+            return Err(anyhow!(
+                "(table: {}) The record with '{}' = {:?} does not exist",
+                rep.get_name(),
+                rep.get_pk_name(),
+                new_val
+            ));
+        }
+        let (rel_name, ..) = self.get_rel();
+        super::relink::nn_by_ofk(conn, rel_name, (col, old_val, new_val), peer_col)
+    }
+
+    /// Move all the links of a record in the first table to another record in the first table,
+    /// the links that the new record already has are not duplicated.
+    /// See also [`super::relink::nn_by_ofk`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `old_val` - the primary key value of the record that gives up its links
+    /// * `new_val` - the primary key value of the record that takes over the links, it must exist
+    /// # Returns
+    /// * `Ok(moved_links)` - the number of links that are moved to the new record
+    pub fn relink_t1(
+        &self,
+        conn: &Connection,
+        old_val: &types::Value,
+        new_val: &types::Value,
+    ) -> anyhow::Result<usize> {
+        self.relink_peers(conn, true, (old_val, new_val))
+    }
+
+    /// Similar to [`NnWrap::relink_t1`], but for the second table.
+    pub fn relink_t2(
+        &self,
+        conn: &Connection,
+        old_val: &types::Value,
+        new_val: &types::Value,
+    ) -> anyhow::Result<usize> {
+        self.relink_peers(conn, false, (old_val, new_val))
+    }
+
+    ///
+    /// the table of one side, its column in the relationship table and the column of the other side,
+    /// the side is chosen explicitly rather than by the table name,
+    /// so that self-referential relationships (e.g. friendships between users) are not ambiguous
+    fn side(&self, is_t1: bool) -> (&'a TblRep, &'a str, &'a str) {
+        let (_, t1_col, t2_col) = self.rel;
+        if is_t1 {
+            (self.t1, t1_col, t2_col)
+        } else {
+            (self.t2, t2_col, t1_col)
+        }
+    }
+
     fn conf_pair<'b: 'a>(
         &'a self,
-        rep: &'b TblRep,
+        is_t1: bool,
         peers: &'b [types::Value],
         is_write: bool,
-    ) -> (NnInfoConfig, NnRelConfig) {
-        let (rel_name, t1_col, t2_col) = self.get_rel();
+    ) -> (NnInfoConfig<'a>, NnRelConfig<'a>) {
+        let (rel_name, ..) = self.get_rel();
+        let (rep, rel_main_col, rel_peer_col) = self.side(is_t1);
         let table = rep.get_name();
-        let (rel_main_col, rel_peer_col) = if table == self.get_t1().get_name() {
-            (t1_col, t2_col)
-        } else {
-            (t2_col, t1_col)
-        };
         let last_info_config_item = if is_write { "" } else { rel_main_col };
         let info_config = (table, rep.get_pk_name(), last_info_config_item);
         let second_col_in_rel_config = if is_write { rel_main_col } else { "" };
@@ -219,12 +424,12 @@ impl<'a> NnWrap<'a> {
     fn ins(
         &self,
         conn: &Connection,
-        rep: &TblRep,
+        is_t1: bool,
         input: &HashMap<String, types::Value>,
         peers: &[types::Value],
         verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
     ) -> anyhow::Result<()> {
-        let (info_config, rel_config) = self.conf_pair(rep, peers, true);
+        let (info_config, rel_config) = self.conf_pair(is_t1, peers, true);
         let info_config = (info_config.0, info_config.1);
         create::nn(conn, input, info_config, rel_config, verification_options)
     }
@@ -242,7 +447,7 @@ impl<'a> NnWrap<'a> {
         peers: &[types::Value],
         verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
     ) -> anyhow::Result<()> {
-        self.ins(conn, self.get_t1(), input, peers, verification_options)
+        self.ins(conn, true, input, peers, verification_options)
     }
 
     /// Inserts a new record into the second table and tie it to the first table.
@@ -258,22 +463,41 @@ impl<'a> NnWrap<'a> {
         peers: &[types::Value],
         verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
     ) -> anyhow::Result<()> {
-        self.ins(conn, self.get_t2(), input, peers, verification_options)
+        self.ins(conn, false, input, peers, verification_options)
     }
 
     fn list_peers(
         &self,
         conn: &Connection,
-        rep: &TblRep,
+        is_t1: bool,
         peers: &[types::Value],
         d_fields: Option<&[&str]>,
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
-        let (info_config, rel_config) = self.conf_pair(rep, peers, false);
+        let (info_config, rel_config) = self.conf_pair(is_t1, peers, false);
         let rel_config = (rel_config.0, rel_config.1, rel_config.3);
         super::fetch::list_n_of_n(conn, info_config, rel_config, d_fields, where_q_config)
     }
 
+    fn count_peers(
+        &self,
+        conn: &Connection,
+        is_t1: bool,
+        peers: &[types::Value],
+        distinct_field: Option<&str>,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> anyhow::Result<i64> {
+        let (info_config, rel_config) = self.conf_pair(is_t1, peers, false);
+        let rel_config = (rel_config.0, rel_config.1, rel_config.3);
+        super::fetch::count_n_of_n(
+            conn,
+            info_config,
+            rel_config,
+            distinct_field,
+            where_q_config,
+        )
+    }
+
     /// List all peers of the given records in the first table.
     /// # Arguments
     /// * `conn` - the Rusqlite connection to the database
@@ -287,7 +511,7 @@ impl<'a> NnWrap<'a> {
         d_fields: Option<&[&str]>,
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
-        self.list_peers(conn, self.get_t1(), peers, d_fields, where_q_config)
+        self.list_peers(conn, true, peers, d_fields, where_q_config)
     }
 
     /// Similar to [`NnWrap::peers_of_t1`], but returns the result as a vector of the given type.
@@ -304,11 +528,38 @@ impl<'a> NnWrap<'a> {
         d_fields: Option<&[&str]>,
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> anyhow::Result<Vec<T>> {
-        let (info_config, rel_config) = self.conf_pair(self.get_t1(), peers, false);
+        let (info_config, rel_config) = self.conf_pair(true, peers, false);
         let rel_config = (rel_config.0, rel_config.1, rel_config.3);
         super::fetch::list_n_of_n_as(conn, info_config, rel_config, d_fields, where_q_config)
     }
 
+    /// Count all peers of the given records in the first table.
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `peers` - the values of the second table's primary key
+    /// * `distinct_field` - if provided, count distinct values in this field
+    /// * `where_q_config` - the where clause and the parameters for condition matching
+    pub fn count_peers_of_t1(
+        &self,
+        conn: &Connection,
+        peers: &[types::Value],
+        distinct_field: Option<&str>,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> anyhow::Result<i64> {
+        self.count_peers(conn, true, peers, distinct_field, where_q_config)
+    }
+
+    /// Similar to [`NnWrap::count_peers_of_t1`], but for the second table.
+    pub fn count_peers_of_t2(
+        &self,
+        conn: &Connection,
+        peers: &[types::Value],
+        distinct_field: Option<&str>,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> anyhow::Result<i64> {
+        self.count_peers(conn, false, peers, distinct_field, where_q_config)
+    }
+
     /// Similar to [`NnWrap::peers_of_t1`], but for the second table.
     pub fn peers_of_t2(
         &self,
@@ -317,7 +568,7 @@ impl<'a> NnWrap<'a> {
         d_fields: Option<&[&str]>,
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> anyhow::Result<Vec<HashMap<String, types::Value>>> {
-        self.list_peers(conn, self.get_t2(), peers, d_fields, where_q_config)
+        self.list_peers(conn, false, peers, d_fields, where_q_config)
     }
 
     /// Similar to [`NnWrap::peers_of_t1_as`], but for the second table.
@@ -328,7 +579,7 @@ impl<'a> NnWrap<'a> {
         d_fields: Option<&[&str]>,
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> anyhow::Result<Vec<T>> {
-        let (info_config, rel_config) = self.conf_pair(self.get_t2(), peers, false);
+        let (info_config, rel_config) = self.conf_pair(false, peers, false);
         let rel_config = (rel_config.0, rel_config.1, rel_config.3);
         super::fetch::list_n_of_n_as(conn, info_config, rel_config, d_fields, where_q_config)
     }
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use rusqlite::{Connection, Transaction};
+
+///
+/// run the operations atomically within a savepoint,
+/// all changes are released if the operations succeed, otherwise they are rolled back.
+/// Savepoints can be nested, so this works both inside and outside of an existing transaction
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `name` - the name of the savepoint
+/// * `ops` - the operations to run, any error will roll back all changes made by them
+pub fn in_savepoint<T>(
+    conn: &Connection,
+    name: &str,
+    ops: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    conn.execute_batch(&format!("SAVEPOINT {}", name))?;
+    match ops() {
+        Ok(result) => {
+            conn.execute_batch(&format!("RELEASE {}", name))?;
+            Ok(result)
+        }
+        Err(err) => {
+            conn.execute_batch(&format!("ROLLBACK TO {}; RELEASE {}", name, name))?;
+            Err(err)
+        }
+    }
+}
+
+///
+/// run multiple operations in one transaction,
+/// the transaction is committed only if all of them succeed, otherwise nothing is changed.
+/// All the helpers of this crate accept `&Connection`, which a `&Transaction` dereferences to
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `ops` - the operations to run with the transaction
+pub fn in_transaction<T>(
+    conn: &mut Connection,
+    ops: impl FnOnce(&Transaction) -> Result<T>,
+) -> Result<T> {
+    let tx = conn.transaction()?;
+    let result = ops(&tx)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+///
+/// run the operations in a transaction that is always rolled back, e.g. to preview the outcome of writes,
+/// the operations can read their own changes, but nothing is persisted
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `ops` - the operations to run with the transaction
+/// # Returns
+/// * the result of the operations, after all their changes are discarded
+pub fn with_rollback<T>(
+    conn: &mut Connection,
+    ops: impl FnOnce(&Transaction) -> Result<T>,
+) -> Result<T> {
+    let tx = conn.transaction()?;
+    let result = ops(&tx);
+    tx.rollback()?;
+    result
+}
@@ -1,8 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
+use anyhow::anyhow;
 use rusqlite::{params_from_iter, types, Connection};
 
-use super::verify::{get_verified_insert_inputs, verify_table_name};
+use super::{
+    atomic::in_savepoint,
+    shift::row_to_map,
+    verify::{get_verified_insert_inputs, verify_table_name, verify_values_required},
+};
 
 ///
 /// insert a new record into the table
@@ -20,13 +25,81 @@ pub fn i_one(
 ) -> anyhow::Result<()> {
     verify_table_name(table_name)?;
     let verified_input = get_verified_insert_inputs(table_name, input, verification_options)?;
+    let (sql, params) = insert_sql(table_name, &verified_input);
+    conn.execute(&sql, params_from_iter(&params))?;
+    Ok(())
+}
+
+///
+/// insert a new record into the table and return the inserted record,
+/// including the values filled by the database such as column defaults or auto-incremented keys.
+/// It requires SQLite 3.35.0 or later because it uses the `RETURNING` clause
+///
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table to insert into
+/// * `input` - the new record to be inserted
+/// * `verification_options` - the options for verification, if None, no verification is performed
+pub fn i_one_returning(
+    conn: &Connection,
+    table_name: &str,
+    input: &HashMap<String, types::Value>,
+    verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
+) -> anyhow::Result<HashMap<String, types::Value>> {
+    verify_table_name(table_name)?;
+    if rusqlite::version_number() < 3_035_000 {
+        return Err(anyhow!(
+            "(table: {}) Returning the inserted record requires SQLite 3.35.0 or later, but the linked version is {}",
+            table_name,
+            rusqlite::version()
+        ));
+    }
+    let verified_input = get_verified_insert_inputs(table_name, input, verification_options)?;
+    let (sql, params) = insert_sql(table_name, &verified_input);
+    let sql = format!("{} RETURNING *", sql);
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(params_from_iter(&params))?;
+    let row = rows.next()?.ok_or(anyhow!(
+        "(table: {}) No record returned from the insertion",
+        table_name
+    ))?;
+    row_to_map(row)
+}
+
+///
+/// generate a random UUID (version 4) in the canonical lowercase text form,
+/// the random bytes come from SQLite's `randomblob`, so no extra dependency is needed
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+pub fn new_uuid(conn: &Connection) -> anyhow::Result<String> {
+    let mut bytes: Vec<u8> = conn.query_row("SELECT randomblob(16)", [], |row| row.get(0))?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+fn insert_sql(
+    table_name: &str,
+    verified_input: &HashMap<String, types::Value>,
+) -> (String, Vec<types::Value>) {
     let mut params = vec![];
     let mut columns = vec![];
     let mut values = vec![];
     for (key, value) in verified_input {
-        columns.push(key);
+        columns.push(key.as_str());
         values.push("?");
-        params.push(value);
+        params.push(value.clone());
     }
 
     let column_expression = columns.join(", ");
@@ -35,7 +108,119 @@ pub fn i_one(
         "INSERT INTO {} ({}) VALUES ({})",
         table_name, column_expression, value_expression
     );
+    (sql, params)
+}
 
+///
+/// insert multiple new records into the table atomically,
+/// if any of them fails, none of them will be inserted
+///
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table to insert into
+/// * `inputs` - the new records to be inserted
+/// * `verification_options` - the options for verification, if None, no verification is performed
+/// # Returns
+/// * `Err` - if any record fails, the error names the index of the first failed record
+pub fn i_many(
+    conn: &Connection,
+    table_name: &str,
+    inputs: &[HashMap<String, types::Value>],
+    verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
+) -> anyhow::Result<()> {
+    verify_table_name(table_name)?;
+    let table_prefix = format!("(table: {}) ", table_name);
+    in_savepoint(conn, "i_many", || {
+        for (index, input) in inputs.iter().enumerate() {
+            i_one(conn, table_name, input, verification_options).map_err(|err| {
+                let err = err.to_string();
+                anyhow!(
+                    "{}The record at index {} cannot be inserted: {}",
+                    table_prefix,
+                    index,
+                    err.strip_prefix(&table_prefix).unwrap_or(&err)
+                )
+            })?;
+        }
+        Ok(())
+    })
+}
+
+///
+/// insert a new record into the table,
+/// or update the existing record if a record with the same primary key already exists.
+/// When updating, only the columns specified in the input are changed, other columns are left untouched
+///
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table to insert into
+/// * `pk_name` - the name of the primary key, the input must contain its value
+/// * `input` - the record to be inserted or updated
+/// * `verification_options` - the options for verification, if None, no verification is performed
+pub fn i_upsert(
+    conn: &Connection,
+    table_name: &str,
+    pk_name: &str,
+    input: &HashMap<String, types::Value>,
+    verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
+) -> anyhow::Result<()> {
+    verify_table_name(table_name)?;
+    let pk_val = input
+        .get(pk_name)
+        .cloned()
+        .unwrap_or(types::Value::Text("".to_string()));
+    verify_values_required(&[pk_val], table_name, pk_name)?;
+    let verified_input = get_verified_insert_inputs(table_name, input, verification_options)?;
+    let (insert_sql, params) = insert_sql(table_name, &verified_input);
+    let mut update_columns = input
+        .keys()
+        .filter(|key| key.as_str() != pk_name)
+        .map(|key| format!("{} = excluded.{}", key, key))
+        .collect::<Vec<_>>();
+    update_columns.sort();
+    let conflict_action = if update_columns.is_empty() {
+        "NOTHING".to_string()
+    } else {
+        format!("UPDATE SET {}", update_columns.join(", "))
+    };
+    let sql = format!(
+        "{} ON CONFLICT({}) DO {}",
+        insert_sql, pk_name, conflict_action
+    );
     conn.execute(&sql, params_from_iter(&params))?;
     Ok(())
 }
+
+///
+/// insert a new record into the table,
+/// or leave the existing record completely untouched if a record with the same primary key already exists,
+/// e.g. for seeding reference data repeatedly.
+/// Unlike `INSERT OR IGNORE`, other constraint violations such as `NOT NULL` still fail
+///
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table to insert into
+/// * `pk_name` - the name of the primary key, the input must contain its value
+/// * `input` - the record to be inserted, it's verified even if it would be ignored
+/// * `verification_options` - the options for verification, if None, no verification is performed
+/// # Returns
+/// * `Ok(inserted_rows)` - 1 if the record is inserted, 0 if it's ignored
+pub fn i_or_ignore(
+    conn: &Connection,
+    table_name: &str,
+    pk_name: &str,
+    input: &HashMap<String, types::Value>,
+    verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
+) -> anyhow::Result<usize> {
+    verify_table_name(table_name)?;
+    let pk_val = input
+        .get(pk_name)
+        .cloned()
+        .unwrap_or(types::Value::Text("".to_string()));
+    verify_values_required(&[pk_val], table_name, pk_name)?;
+    let verified_input = get_verified_insert_inputs(table_name, input, verification_options)?;
+    let (insert_sql, params) = insert_sql(table_name, &verified_input);
+    let sql = format!("{} ON CONFLICT({}) DO NOTHING", insert_sql, pk_name);
+    let inserted_rows = conn.execute(&sql, params_from_iter(&params))?;
+    Ok(inserted_rows)
+}
@@ -1,10 +1,15 @@
 use super::{
-    sql,
+    atomic::in_savepoint,
+    fetch, sql,
     verify::{verify_table_name, verify_values_required},
 };
 
 use rusqlite::{params_from_iter, types, Connection};
 
+///
+/// the column that marks a record as soft-deleted, see [`d_soft_by_pk`]
+pub const SOFT_DEL_COL: &str = "deleted_at";
+
 ///
 /// delete all matching records from the table that meet the conditions.
 ///
@@ -13,20 +18,21 @@ use rusqlite::{params_from_iter, types, Connection};
 /// * `conn` - the Rusqlite connection to the database
 /// * `table_name` - the name of the table
 /// * `where_q_config` - the where clause and the parameters for the where clause,
-///                      to reduce the chance of unwanted deletions,
-///                      this is not an Option and cannot contain empty clause
+///   to reduce the chance of unwanted deletions,
+///   this is not an Option and cannot contain empty clause
 /// # Returns
+/// * `Ok(affected_rows)` - the number of records that are deleted
 pub fn d_all(
     conn: &Connection,
     table_name: &str,
     where_q_config: (&str, &[types::Value]),
-) -> anyhow::Result<()> {
+) -> anyhow::Result<usize> {
     verify_table_name(table_name)?;
     let (where_clause, where_params) = sql::standardize_q_config(Some(where_q_config), "WHERE")?;
     let sql = format!("DELETE FROM {} {}", table_name, where_clause);
     let mut stmt = conn.prepare(&sql)?;
-    stmt.execute(params_from_iter(&where_params))?;
-    Ok(())
+    let affected_rows = stmt.execute(params_from_iter(&where_params))?;
+    Ok(affected_rows)
 }
 
 ///
@@ -37,20 +43,87 @@ pub fn d_all(
 /// * `pk_name` - the name of the primary key
 /// * `pk_values` - records to be deleted represented by their primary key values
 /// * `where_q_config` - the extra where clause and the parameters for
-///                   the where clause apart from the primary key values
+///   the where clause apart from the primary key values
+///
+/// A large list of primary key values is deleted in chunks within a savepoint, see [`super::fetch::f_by_pk`]
+/// # Returns
+/// * `Ok(affected_rows)` - the number of records that are deleted
 pub fn d_by_pk(
     conn: &Connection,
     table_name: &str,
     pk_name: &str,
     pk_values: &[types::Value],
     where_q_config: Option<(&str, &[types::Value])>,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<usize> {
+    verify_values_required(pk_values, table_name, pk_name)?;
+    verify_table_name(table_name)?;
+    let chunk_size = fetch::fit_pk_chunk_size(where_q_config);
+    let pk_chunks = sql::in_them_chunks(pk_name, pk_values, chunk_size, false);
+    in_savepoint(conn, "d_by_pk", || {
+        let mut affected_rows = 0;
+        for (pk_query_clause, pk_query_params) in &pk_chunks {
+            let pk_where_refs = (pk_query_clause.as_str(), pk_query_params.as_slice());
+            let (where_clause, where_params) =
+                sql::merge_q_configs(Some(pk_where_refs), where_q_config, "AND")?;
+            affected_rows += d_all(conn, table_name, (where_clause.as_str(), &where_params))?;
+        }
+        Ok(affected_rows)
+    })
+}
+
+///
+/// soft-delete records from the table by looking for their primary keys,
+/// instead of removing them, their [`SOFT_DEL_COL`] is set to `CURRENT_TIMESTAMP`.
+/// Records that are already soft-deleted keep their original timestamps,
+/// a NULL or empty [`SOFT_DEL_COL`] means the record is not deleted.
+/// To exclude soft-deleted records from reads, see [`not_soft_deleted`],
+/// the reads of [`crate::TblRep`] exclude them by default, see [`crate::TblRep::set_with_soft_deleted`]
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table, it must have the [`SOFT_DEL_COL`] column
+/// * `pk_name` - the name of the primary key
+/// * `pk_values` - records to be deleted represented by their primary key values
+/// * `where_q_config` - the extra where clause and the parameters for
+///   the where clause apart from the primary key values
+/// # Returns
+/// * `Ok(affected_rows)` - the number of records that are newly soft-deleted
+///
+/// A large list of primary key values is soft-deleted in chunks within a savepoint, see [`d_by_pk`]
+pub fn d_soft_by_pk(
+    conn: &Connection,
+    table_name: &str,
+    pk_name: &str,
+    pk_values: &[types::Value],
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> anyhow::Result<usize> {
     verify_values_required(pk_values, table_name, pk_name)?;
     verify_table_name(table_name)?;
-    let (pk_query_clause, pk_query_params) = sql::in_them(pk_name, pk_values);
-    let pk_where_refs = (pk_query_clause.as_str(), pk_query_params.as_slice());
-    let (where_clause, where_params) =
-        sql::merge_q_configs(Some(pk_where_refs), where_q_config, "AND")?;
-    d_all(conn, table_name, (where_clause.as_str(), &where_params))?;
-    Ok(())
+    let chunk_size = fetch::fit_pk_chunk_size(where_q_config);
+    let pk_chunks = sql::in_them_chunks(pk_name, pk_values, chunk_size, false);
+    in_savepoint(conn, "d_soft_by_pk", || {
+        let mut affected_rows = 0;
+        for (pk_query_clause, pk_query_params) in &pk_chunks {
+            let pk_where_refs = (pk_query_clause.as_str(), pk_query_params.as_slice());
+            let (where_clause, where_params) =
+                sql::merge_q_configs(Some(pk_where_refs), where_q_config, "AND")?;
+            let sql = format!(
+                "UPDATE {} SET {} = CURRENT_TIMESTAMP WHERE ({}) AND {}",
+                table_name,
+                SOFT_DEL_COL,
+                where_clause,
+                not_soft_deleted().0
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            affected_rows += stmt.execute(params_from_iter(&where_params))?;
+        }
+        Ok(affected_rows)
+    })
+}
+
+///
+/// the condition that excludes soft-deleted records, see [`d_soft_by_pk`],
+/// it can be merged with other conditions by [`sql::merge_q_configs`]
+pub fn not_soft_deleted() -> (String, Vec<types::Value>) {
+    let clause = format!("({} IS NULL OR {} = '')", SOFT_DEL_COL, SOFT_DEL_COL);
+    (clause, vec![])
 }
@@ -1,11 +1,11 @@
 use super::{
-    shift::{self, row_to_map},
+    shift::{self, row_to_map, row_to_pairs},
     sql,
-    verify::verify_table_name,
+    verify::{is_empty, verify_chunk_size, verify_table_name},
 };
 
-use anyhow::Result;
-use rusqlite::{params_from_iter, types, Connection};
+use anyhow::{anyhow, Result};
+use rusqlite::{params_from_iter, types, Connection, Row};
 use serde::de::DeserializeOwned;
 
 use std::collections::HashMap;
@@ -22,8 +22,70 @@ pub fn f_all(
     conn: &Connection,
     table_name: &str,
     where_q_config: Option<(&str, &[types::Value])>,
-    (is_distinct, display_fields): (bool, Option<&[&str]>),
+    display_opt: (bool, Option<&[&str]>),
 ) -> Result<Vec<HashMap<String, types::Value>>> {
+    let mut result = Vec::new();
+    f_each(conn, table_name, where_q_config, display_opt, |record| {
+        result.push(record);
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+///
+/// visit all matching records from the table one at a time,
+/// the records are not collected, so the memory usage stays flat for large tables.
+/// See also [`f_all`]
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `where_q_config` - the where clause and the parameters for the where clause
+/// * `is_distinct` - whether to use the DISTINCT keyword in the SQL query
+/// * `display_fields` - the fields to be displayed in the result
+/// * `on_record` - called with each record, an error stops the iteration and is returned
+pub fn f_each(
+    conn: &Connection,
+    table_name: &str,
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_opt: (bool, Option<&[&str]>),
+    mut on_record: impl FnMut(HashMap<String, types::Value>) -> Result<()>,
+) -> Result<()> {
+    each_row(conn, table_name, where_q_config, display_opt, |row| {
+        on_record(row_to_map(row)?)
+    })
+}
+
+///
+/// fetch all matching records from the table, each record is a list of `(column, value)` pairs
+/// in the order of the columns in the table, or in the order of `display_fields` if provided.
+/// See also [`f_all`]
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `where_q_config` - the where clause and the parameters for the where clause
+/// * `is_distinct` - whether to use the DISTINCT keyword in the SQL query
+/// * `display_fields` - the fields to be displayed in the result
+pub fn f_all_ordered(
+    conn: &Connection,
+    table_name: &str,
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_opt: (bool, Option<&[&str]>),
+) -> Result<Vec<Vec<(String, types::Value)>>> {
+    let mut result = Vec::new();
+    each_row(conn, table_name, where_q_config, display_opt, |row| {
+        result.push(row_to_pairs(row)?);
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+fn each_row(
+    conn: &Connection,
+    table_name: &str,
+    where_q_config: Option<(&str, &[types::Value])>,
+    (is_distinct, display_fields): (bool, Option<&[&str]>),
+    mut on_row: impl FnMut(&Row) -> Result<()>,
+) -> Result<()> {
     verify_table_name(table_name)?;
     let default_fields = vec!["*"];
     let display_fields = match display_fields {
@@ -39,15 +101,128 @@ pub fn f_all(
     );
     let (where_q_clause, where_q_params) = sql::standardize_q_config(where_q_config, "WHERE")?;
     let sql = format!("{} {}", sql, where_q_clause);
-    let mut stmt = conn.prepare(&sql)?;
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let mut rows = stmt.query(params_from_iter(&where_q_params))?;
+    while let Some(row) = rows.next()? {
+        on_row(row)?;
+    }
+    Ok(())
+}
+
+///
+/// fetch the distinct values of a column, e.g. to populate a filter dropdown
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `col_name` - the name of the column
+/// * `where_q_config` - the where clause and the parameters for the where clause
+/// * `is_sorted` - whether to sort the values in ascending order
+/// * `skip_empty` - whether to exclude NULL, blank text and empty blob values
+/// # Returns
+/// * `Ok(Vec<value>)` - the distinct values themselves rather than records
+pub fn f_distinct(
+    conn: &Connection,
+    table_name: &str,
+    col_name: &str,
+    where_q_config: Option<(&str, &[types::Value])>,
+    (is_sorted, skip_empty): (bool, bool),
+) -> Result<Vec<types::Value>> {
+    verify_table_name(table_name)?;
+    let (where_q_clause, where_q_params) = sql::standardize_q_config(where_q_config, "WHERE")?;
+    let order_clause = if is_sorted {
+        format!("ORDER BY {}", col_name)
+    } else {
+        String::new()
+    };
+    let sql = format!(
+        "SELECT DISTINCT {} FROM {} {} {}",
+        col_name, table_name, where_q_clause, order_clause
+    );
+    let mut stmt = conn.prepare_cached(&sql)?;
     let mut rows = stmt.query(params_from_iter(&where_q_params))?;
     let mut result = Vec::new();
     while let Some(row) = rows.next()? {
-        result.push(row_to_map(row)?);
+        let value: types::Value = row.get(0)?;
+        if skip_empty && is_empty(&value) {
+            continue;
+        }
+        result.push(value);
     }
     Ok(result)
 }
 
+///
+/// `tuple(records, next_cursor)`, see [`f_after`]
+pub type KeysetPage = (Vec<HashMap<String, types::Value>>, Option<types::Value>);
+
+///
+/// fetch a page of matching records that come after a cursor in the order of a column (keyset pagination),
+/// unlike offset pagination, the pages don't drift when records are inserted or deleted meanwhile
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `page_config` - `tuple(order_column, cursor, is_descending, page_size)`
+///   - the values of the order column should be unique and not NULL, e.g. the primary key
+///   - the cursor is the value returned with the previous page, `None` for the first page
+/// * `where_q_config` - the where clause and the parameters for the where clause
+/// * `display_fields` - the fields to be displayed in the result, the order column is always included
+/// # Returns
+/// * `tuple(records, next_cursor)` - `next_cursor` is `None` if there are no more records
+pub fn f_after(
+    conn: &Connection,
+    table_name: &str,
+    (order_col, cursor, is_desc, page_size): (&str, Option<&types::Value>, bool, u32),
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_fields: Option<&[&str]>,
+) -> Result<KeysetPage> {
+    verify_table_name(table_name)?;
+    if page_size == 0 {
+        return Err(anyhow!(
+            "(table: {}) At least 1 record per page is required for this operation",
+            table_name
+        ));
+    }
+    let display_fields = match display_fields {
+        Some(fields) if fields.contains(&order_col) => fields.join(", "),
+        Some(fields) => [fields, &[order_col]].concat().join(", "),
+        None => "*".to_string(),
+    };
+    let (compare_word, order_word) = if is_desc { ("<", "DESC") } else { (">", "ASC") };
+    let cursor_clause = format!("{} {} ?", order_col, compare_word);
+    let cursor_q_config =
+        cursor.map(|cursor| (cursor_clause.as_str(), std::slice::from_ref(cursor)));
+    let (where_clause, where_params) = match cursor_q_config {
+        Some(cursor_q_config) => {
+            sql::merge_q_configs(Some(cursor_q_config), where_q_config, "AND")?
+        }
+        None => sql::standardize_q_config(where_q_config, "")?,
+    };
+    let where_clause = if where_clause.trim().is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", where_clause)
+    };
+    let sql = format!(
+        "SELECT {} FROM {} {} ORDER BY {} {} LIMIT ?",
+        display_fields, table_name, where_clause, order_col, order_word
+    );
+    let params = [where_params, vec![types::Value::Integer(page_size as i64)]].concat();
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let mut rows = stmt.query(params_from_iter(&params))?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        result.push(row_to_map(row)?);
+    }
+    let next_cursor = if result.len() < page_size as usize {
+        None
+    } else {
+        result
+            .last()
+            .and_then(|record| record.get(order_col).cloned())
+    };
+    Ok((result, next_cursor))
+}
+
 pub fn f_all_as<T: DeserializeOwned>(
     conn: &Connection,
     table_name: &str,
@@ -56,33 +231,230 @@ pub fn f_all_as<T: DeserializeOwned>(
 ) -> Result<Vec<T>> {
     let display_opt = (is_distinct, display_fields);
     let rows = f_all(conn, table_name, where_q_config, display_opt)?;
-    let mut result = Vec::new();
-    for row in &rows {
-        result.push(serde_json::from_value(shift::val_to_json(row)?)?);
-    }
-    Ok(result)
+    shift::list_as(table_name, &rows)
 }
 
+///
+/// the default maximum number of primary key values in a single `IN` clause of [`f_by_pk`],
+/// it stays well below [`sql::MAX_BOUND_PARAMS`],
+/// and is shrunk when needed to leave room for the parameters of the other conditions
+pub const PK_CHUNK_SIZE: usize = 500;
+
 pub fn f_by_pk(
     conn: &Connection,
     table_name: &str,
-    (pk_name, pk_values): (&str, &[types::Value]),
+    pk_config: (&str, &[types::Value]),
     where_q_config: Option<(&str, &[types::Value])>,
     display_fields: Option<&[&str]>,
 ) -> Result<Vec<HashMap<String, types::Value>>> {
-    let (pk_find_clause, pk_find_params) = sql::in_them(pk_name, pk_values);
-    let pk_find_refs = (pk_find_clause.as_str(), pk_find_params.as_slice());
-    let (where_clause, where_params) =
-        sql::merge_q_configs(Some(pk_find_refs), where_q_config, "AND")?;
-    let result = f_all(
+    f_by_pk_in_chunks(
         conn,
         table_name,
-        Some((where_clause.as_str(), &where_params)),
-        (false, display_fields),
+        pk_config,
+        where_q_config,
+        display_fields,
+        fit_pk_chunk_size(where_q_config),
+    )
+}
+
+///
+/// the default chunk size of primary key values that leaves room for the parameters of the other conditions
+pub(crate) fn fit_pk_chunk_size(where_q_config: Option<(&str, &[types::Value])>) -> usize {
+    let other_param_count = where_q_config.map_or(0, |(_, params)| params.len());
+    sql::fit_chunk_size(PK_CHUNK_SIZE, other_param_count)
+}
+
+///
+/// the same as [`f_by_pk`], but the records are keyed by the text form of their primary key values,
+/// e.g. for patching the fetched records in memory.
+/// Integer and real values are keyed by their decimal form, so `1` and `"1"` share the same key
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `pk_config` - `tuple(pk_name, pk_values)`, the primary key values must be integers, real numbers or text
+/// * `where_q_config` - the where clause and the parameters for the where clause
+/// * `display_fields` - the fields to be displayed in the result, the primary key is always included
+/// # Returns
+/// * `Err` - if 2 records share the same key
+pub fn f_by_pk_keyed(
+    conn: &Connection,
+    table_name: &str,
+    pk_config: (&str, &[types::Value]),
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_fields: Option<&[&str]>,
+) -> Result<HashMap<String, HashMap<String, types::Value>>> {
+    let (pk_name, _) = pk_config;
+    let display_fields = display_fields.map(|fields| {
+        if fields.contains(&pk_name) {
+            fields.to_vec()
+        } else {
+            [fields, &[pk_name]].concat()
+        }
+    });
+    let records = f_by_pk(
+        conn,
+        table_name,
+        pk_config,
+        where_q_config,
+        display_fields.as_deref(),
     )?;
+    let mut result = HashMap::with_capacity(records.len());
+    for record in records {
+        let key = match record.get(pk_name) {
+            Some(types::Value::Integer(int)) => int.to_string(),
+            Some(types::Value::Real(float)) => float.to_string(),
+            Some(types::Value::Text(text)) => text.clone(),
+            pk_value => {
+                return Err(anyhow!(
+                    "(table: {}) The value of '{}' cannot be used as a key: {:?}",
+                    table_name,
+                    pk_name,
+                    pk_value
+                ))
+            }
+        };
+        if result.contains_key(&key) {
+            return Err(anyhow!(
+                "(table: {}) The key '{}' of '{}' is shared by more than 1 record",
+                table_name,
+                key,
+                pk_name
+            ));
+        }
+        result.insert(key, record);
+    }
+    Ok(result)
+}
+
+///
+/// the same as [`f_by_pk`], but with a custom chunk size.
+/// When there are more primary key values than `chunk_size`,
+/// the duplicated values are removed and each chunk of them is fetched by a separate statement,
+/// so a large number of keys never exceeds the limit of bound parameters
+/// # Arguments
+/// * `chunk_size` - the maximum number of primary key values in a single statement, it must be positive,
+///   and together with the parameters of `where_q_config` it cannot exceed [`sql::MAX_BOUND_PARAMS`]
+pub fn f_by_pk_in_chunks(
+    conn: &Connection,
+    table_name: &str,
+    pk_config: (&str, &[types::Value]),
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_fields: Option<&[&str]>,
+    chunk_size: usize,
+) -> Result<Vec<HashMap<String, types::Value>>> {
+    f_pk_chunks(
+        conn,
+        table_name,
+        pk_config,
+        where_q_config,
+        display_fields,
+        (chunk_size, false),
+    )
+}
+
+///
+/// the same as [`f_by_pk`], but the `IN` list of primary key values is built by [`sql::in_them_padded`],
+/// so the reads with lists of similar sizes share the same SQL and its cached prepared statement.
+/// It only pays off when the reads use more distinct numbers of keys than the statement cache of the connection holds,
+/// otherwise the repeated values are bound for nothing, see the ignored `bench_repeated_pk_reads` test
+pub fn f_by_pk_cached(
+    conn: &Connection,
+    table_name: &str,
+    pk_config: (&str, &[types::Value]),
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_fields: Option<&[&str]>,
+) -> Result<Vec<HashMap<String, types::Value>>> {
+    f_pk_chunks(
+        conn,
+        table_name,
+        pk_config,
+        where_q_config,
+        display_fields,
+        (fit_pk_chunk_size(where_q_config), true),
+    )
+}
+
+fn f_pk_chunks(
+    conn: &Connection,
+    table_name: &str,
+    (pk_name, pk_values): (&str, &[types::Value]),
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_fields: Option<&[&str]>,
+    (chunk_size, is_padded): (usize, bool),
+) -> Result<Vec<HashMap<String, types::Value>>> {
+    let other_param_count = where_q_config.map_or(0, |(_, params)| params.len());
+    verify_chunk_size(table_name, chunk_size, other_param_count)?;
+    let mut result = vec![];
+    for (pk_find_clause, pk_find_params) in
+        sql::in_them_chunks(pk_name, pk_values, chunk_size, is_padded)
+    {
+        let pk_find_refs = (pk_find_clause.as_str(), pk_find_params.as_slice());
+        let (where_clause, where_params) =
+            sql::merge_q_configs(Some(pk_find_refs), where_q_config, "AND")?;
+        result.extend(f_all(
+            conn,
+            table_name,
+            Some((where_clause.as_str(), &where_params)),
+            (false, display_fields),
+        )?);
+    }
     Ok(result)
 }
 
+///
+/// fetch the only matching record from the table
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `where_q_config` - the where clause and the parameters for the where clause
+/// * `display_fields` - the fields to be displayed in the result
+/// # Returns
+/// * `Ok(None)` - if no record matches
+/// * `Err` - if more than 1 record matches
+pub fn f_one(
+    conn: &Connection,
+    table_name: &str,
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_fields: Option<&[&str]>,
+) -> Result<Option<HashMap<String, types::Value>>> {
+    let rows = f_all(conn, table_name, where_q_config, (false, display_fields))?;
+    at_most_one(table_name, rows)
+}
+
+///
+/// fetch a record from the table by its primary key, see also [`f_one`]
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `pk_config` - `tuple(primary_key_name, primary_key_value)`
+/// * `where_q_config` - the extra where clause and the parameters apart from the primary key value
+/// * `display_fields` - the fields to be displayed in the result
+pub fn f_one_by_pk(
+    conn: &Connection,
+    table_name: &str,
+    (pk_name, pk_value): (&str, &types::Value),
+    where_q_config: Option<(&str, &[types::Value])>,
+    display_fields: Option<&[&str]>,
+) -> Result<Option<HashMap<String, types::Value>>> {
+    let pk_config = (pk_name, std::slice::from_ref(pk_value));
+    let rows = f_by_pk(conn, table_name, pk_config, where_q_config, display_fields)?;
+    at_most_one(table_name, rows)
+}
+
+fn at_most_one(
+    table_name: &str,
+    rows: Vec<HashMap<String, types::Value>>,
+) -> Result<Option<HashMap<String, types::Value>>> {
+    if rows.len() > 1 {
+        return Err(anyhow!(
+            "(table: {}) At most 1 record is expected, but {} records are found",
+            table_name,
+            rows.len()
+        ));
+    }
+    Ok(rows.into_iter().next())
+}
+
 pub fn f_by_pk_as<T: DeserializeOwned>(
     conn: &Connection,
     table_name: &str,
@@ -91,11 +463,7 @@ pub fn f_by_pk_as<T: DeserializeOwned>(
     display_fields: Option<&[&str]>,
 ) -> Result<Vec<T>> {
     let rows = f_by_pk(conn, table_name, pk_config, where_q_config, display_fields)?;
-    let mut result = Vec::new();
-    for row in &rows {
-        result.push(serde_json::from_value(shift::val_to_json(row)?)?);
-    }
-    Ok(result)
+    shift::list_as(table_name, &rows)
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+pub mod atomic;
 pub mod create;
 pub mod del;
 pub mod fetch;
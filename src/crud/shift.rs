@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use rusqlite::{types, Row};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 
 pub fn row_to_map(row: &Row) -> Result<HashMap<String, types::Value>> {
@@ -11,6 +12,27 @@ pub fn row_to_map(row: &Row) -> Result<HashMap<String, types::Value>> {
     Ok(map)
 }
 
+///
+/// the same as [`row_to_map`], but the columns keep the order of the `SELECT` clause,
+/// e.g. for CSV exports whose columns must not shuffle between runs
+pub fn row_to_pairs(row: &Row) -> Result<Vec<(String, types::Value)>> {
+    let mut pairs = Vec::new();
+    for (i, column_name) in row.as_ref().column_names().iter().enumerate() {
+        pairs.push((column_name.to_string(), row.get(i)?));
+    }
+    Ok(pairs)
+}
+
+/// How blob values are rendered in JSON
+/// * `ByteArray` - an array of byte integers, e.g. `[104, 105]`
+/// * `Base64` - a standard base64 string with padding, e.g. `"aGk="`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlobEncoding {
+    #[default]
+    ByteArray,
+    Base64,
+}
+
 /// Convert a HashMap containing a rusqlite record to a serde_json::Value
 /// So that it can be used in JSON related functionalities
 /// # Arguments
@@ -18,6 +40,19 @@ pub fn row_to_map(row: &Row) -> Result<HashMap<String, types::Value>> {
 /// # Returns
 /// * `serde_json::Value` - the JSON representation of the record
 pub fn val_to_json(map: &HashMap<String, types::Value>) -> Result<serde_json::Value> {
+    val_to_json_with(map, BlobEncoding::default())
+}
+
+/// The same as [`val_to_json`], but the blobs are rendered with the given encoding
+/// # Arguments
+/// * `map` - the HashMap containing the rusqlite record
+/// * `blob_encoding` - how the blob values are rendered, see [`BlobEncoding`]
+/// # Returns
+/// * `serde_json::Value` - the JSON representation of the record
+pub fn val_to_json_with(
+    map: &HashMap<String, types::Value>,
+    blob_encoding: BlobEncoding,
+) -> Result<serde_json::Value> {
     let mut json_map = serde_json::Map::new();
     for (key, value) in map.iter() {
         let json_value = match value {
@@ -27,18 +62,141 @@ pub fn val_to_json(map: &HashMap<String, types::Value>) -> Result<serde_json::Va
                 serde_json::Number::from_f64(*float).ok_or(anyhow!("Invalid float"))?,
             ),
             types::Value::Text(text) => serde_json::Value::String(text.to_string()),
-            types::Value::Blob(blob) => serde_json::Value::Array(
-                blob.to_vec()
-                    .iter()
-                    .map(|b| serde_json::Value::Number(serde_json::Number::from(*b)))
-                    .collect(),
-            ),
+            types::Value::Blob(blob) => match blob_encoding {
+                BlobEncoding::ByteArray => serde_json::Value::Array(
+                    blob.to_vec()
+                        .iter()
+                        .map(|b| serde_json::Value::Number(serde_json::Number::from(*b)))
+                        .collect(),
+                ),
+                BlobEncoding::Base64 => serde_json::Value::String(to_base64(blob)),
+            },
         };
         json_map.insert(key.to_string(), json_value);
     }
     Ok(serde_json::Value::Object(json_map))
 }
 
+fn to_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, b)| acc | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (triple >> (18 - 6 * i)) & 0x3f;
+                encoded.push(ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Convert a list of rusqlite records to a list of JSON objects, see [`val_to_json_with`]
+/// # Arguments
+/// * `rows` - the HashMaps containing the rusqlite records
+/// * `blob_encoding` - how the blob values are rendered, see [`BlobEncoding`]
+pub fn rows_to_json_with(
+    rows: &[HashMap<String, types::Value>],
+    blob_encoding: BlobEncoding,
+) -> Result<Vec<serde_json::Value>> {
+    rows.iter()
+        .map(|row| val_to_json_with(row, blob_encoding))
+        .collect()
+}
+
+/// Convert a list of rusqlite records to a list of the given type
+/// # Arguments
+/// * `table_name` - the name of the table that the records are fetched from, it's used in the error messages
+/// * `rows` - the HashMaps containing the rusqlite records
+/// * `T` - the type of each element in the result
+/// # Returns
+/// * `Err` - if any record cannot be converted, the error names the index of the record
+///   and the field that fails, without exposing the values of the other fields
+pub fn list_as<T: DeserializeOwned>(
+    table_name: &str,
+    rows: &[HashMap<String, types::Value>],
+) -> Result<Vec<T>> {
+    list_as_with(table_name, rows, BlobEncoding::default())
+}
+
+/// The same as [`list_as`], but the blobs are converted with the given encoding,
+/// e.g. [`BlobEncoding::Base64`] for a `String` field
+/// # Arguments
+/// * `blob_encoding` - how the blob values are rendered, see [`BlobEncoding`]
+pub fn list_as_with<T: DeserializeOwned>(
+    table_name: &str,
+    rows: &[HashMap<String, types::Value>],
+    blob_encoding: BlobEncoding,
+) -> Result<Vec<T>> {
+    let mut result = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let item = record_as(row, blob_encoding).map_err(|(field, err)| match field {
+            Some(field) => anyhow!(
+                "(table: {}) The record at index {} cannot be converted at the field '{}': {}",
+                table_name,
+                index,
+                field,
+                err
+            ),
+            None => anyhow!(
+                "(table: {}) The record at index {} cannot be converted: {}",
+                table_name,
+                index,
+                err
+            ),
+        })?;
+        result.push(item);
+    }
+    Ok(result)
+}
+
+///
+/// convert a record with serde_json, the record is rendered on one line
+/// so that the position of a failure can be traced back to the field being converted,
+/// a failure that is not caused by any field (e.g. a missing field) is not tied to a field
+fn record_as<T: DeserializeOwned>(
+    row: &HashMap<String, types::Value>,
+    blob_encoding: BlobEncoding,
+) -> std::result::Result<T, (Option<String>, anyhow::Error)> {
+    let json = match val_to_json_with(row, blob_encoding) {
+        Ok(serde_json::Value::Object(json_map)) => json_map,
+        Ok(_) => return Err((None, anyhow!("The record is not a JSON object"))),
+        Err(err) => return Err((None, err)),
+    };
+    let mut text = String::from("{");
+    let mut spans = Vec::with_capacity(json.len());
+    for (index, (key, value)) in json.iter().enumerate() {
+        if index > 0 {
+            text.push(',');
+        }
+        text.push_str(&serde_json::Value::String(key.clone()).to_string());
+        text.push(':');
+        let start = text.len();
+        text.push_str(&value.to_string());
+        spans.push((key, start, text.len()));
+    }
+    text.push('}');
+    serde_json::from_str(&text).map_err(|err| {
+        // the column is 1-based and counts bytes, it points at the end of a failed value,
+        // or at the colon before it when the value is a sequence or a map
+        let column = err.column();
+        let field = spans
+            .iter()
+            .find(|(_, start, end)| (*start..=*end).contains(&column))
+            .map(|(key, _, _)| key.to_string());
+        let position = format!(" at line {} column {}", err.line(), column);
+        let message = err.to_string();
+        let message = message.strip_suffix(&position).unwrap_or(&message);
+        (field, anyhow!("{}", message))
+    })
+}
+
 pub mod val {
     use rusqlite::types;
 
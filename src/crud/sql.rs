@@ -1,6 +1,13 @@
 use anyhow::{anyhow, Result};
 use rusqlite::types;
 
+use super::verify::are_same_type;
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
 pub fn in_them(col_name: &str, col_values: &[types::Value]) -> (String, Vec<types::Value>) {
     let pk_value_placeholders = col_values
         .iter()
@@ -11,6 +18,367 @@ pub fn in_them(col_name: &str, col_values: &[types::Value]) -> (String, Vec<type
     (clause, col_values.to_vec())
 }
 
+///
+/// the same as [`in_them`], but the number of placeholders is padded to the next power of 2
+/// by repeating the last value, which doesn't change the matching records.
+/// Lists of similar sizes share the same SQL, so the cached prepared statements can be reused,
+/// see [`crate::crud::fetch::f_by_pk_cached`]
+/// # Arguments
+/// * `col_name` - the name of the column
+/// * `col_values` - the values to match
+/// * `max_len` - the padding never goes past this number of placeholders,
+///   e.g. to stay within the limit of bound parameters
+pub fn in_them_padded(
+    col_name: &str,
+    col_values: &[types::Value],
+    max_len: usize,
+) -> (String, Vec<types::Value>) {
+    let mut padded_values = col_values.to_vec();
+    if let Some(last_value) = col_values.last() {
+        let padded_len = col_values
+            .len()
+            .next_power_of_two()
+            .min(max_len.max(col_values.len()));
+        padded_values.resize(padded_len, last_value.clone());
+    }
+    in_them(col_name, &padded_values)
+}
+
+///
+/// the maximum number of bound parameters in a single statement of older SQLite builds,
+/// newer builds allow more, but staying within it keeps the statements portable
+pub const MAX_BOUND_PARAMS: usize = 999;
+
+///
+/// split the values into [`in_them`] conditions of at most `chunk_size` values each,
+/// so that each of them can be run by a separate statement without exceeding [`MAX_BOUND_PARAMS`].
+/// The duplicated values are always removed first, see [`dedup_values`],
+/// so no record is matched by more than 1 chunk because of a repeated value.
+/// An empty list still gives 1 condition, which matches nothing
+/// # Arguments
+/// * `col_name` - the name of the column
+/// * `col_values` - the values to match
+/// * `chunk_size` - the maximum number of values in a condition, it must be positive,
+///   see [`crate::crud::verify::verify_chunk_size`]
+/// * `is_padded` - whether each condition is built by [`in_them_padded`] without going past `chunk_size`
+pub fn in_them_chunks(
+    col_name: &str,
+    col_values: &[types::Value],
+    chunk_size: usize,
+    is_padded: bool,
+) -> Vec<(String, Vec<types::Value>)> {
+    let chunk_size = chunk_size.max(1);
+    let col_values = dedup_values(col_values);
+    if col_values.is_empty() {
+        return vec![in_them(col_name, &col_values)];
+    }
+    col_values
+        .chunks(chunk_size)
+        .map(|chunk| {
+            if is_padded {
+                in_them_padded(col_name, chunk, chunk_size)
+            } else {
+                in_them(col_name, chunk)
+            }
+        })
+        .collect()
+}
+
+///
+/// the hashable form of a [`types::Value`], `-0.0` and `0.0` share the same key
+/// because they are equal as values
+#[derive(PartialEq, Eq, Hash)]
+enum ValueKey<'a> {
+    Null,
+    Integer(i64),
+    Real(u64),
+    Text(&'a str),
+    Blob(&'a [u8]),
+}
+
+impl<'a> From<&'a types::Value> for ValueKey<'a> {
+    fn from(value: &'a types::Value) -> Self {
+        match value {
+            types::Value::Null => ValueKey::Null,
+            types::Value::Integer(int) => ValueKey::Integer(*int),
+            types::Value::Real(float) if *float == 0.0 => ValueKey::Real(0.0f64.to_bits()),
+            types::Value::Real(float) => ValueKey::Real(float.to_bits()),
+            types::Value::Text(text) => ValueKey::Text(text),
+            types::Value::Blob(blob) => ValueKey::Blob(blob),
+        }
+    }
+}
+
+///
+/// remove the duplicated values, keeping the first occurrence of each value in its original order.
+/// Values of different types are never duplicates, e.g. `1` and `"1"` are both kept
+/// # Arguments
+/// * `values` - the values to deduplicate
+pub fn dedup_values(values: &[types::Value]) -> Vec<types::Value> {
+    let mut seen = HashSet::with_capacity(values.len());
+    values
+        .iter()
+        .filter(|value| seen.insert(ValueKey::from(*value)))
+        .cloned()
+        .collect()
+}
+
+///
+/// shrink the chunk size for [`in_them_chunks`] when needed,
+/// so that a chunk and the other parameters of the statement stay within [`MAX_BOUND_PARAMS`]
+pub(crate) fn fit_chunk_size(chunk_size: usize, other_param_count: usize) -> usize {
+    chunk_size
+        .min(MAX_BOUND_PARAMS.saturating_sub(other_param_count))
+        .max(1)
+}
+
+///
+/// build an equality condition for multiple columns joined by `AND`, e.g. `(col1 = ? AND col2 = ?)`,
+/// i.e. a [`Filter::And`] of [`CmpOp::Eq`] compiled by [`filter_q_config`],
+/// the result can be merged with other conditions by [`merge_q_configs`].
+/// Note that `col = NULL` never matches, use [`not_distinct_from`] to match NULL values
+/// # Arguments
+/// * `pairs` - pairs of column names and the values to match, it cannot be empty
+/// * `columns` - see `columns` of [`filter_q_config`]
+pub fn eq_all(
+    pairs: &[(&str, types::Value)],
+    columns: Option<&HashMap<String, types::Value>>,
+) -> Result<(String, Vec<types::Value>)> {
+    if pairs.is_empty() {
+        return Err(anyhow!(
+            "At least 1 column is required for equality conditions, if you don't need it, specify the `q_config` as None"
+        ));
+    }
+    let filter = Filter::And(
+        pairs
+            .iter()
+            .map(|(col_name, value)| Filter::Cmp(col_name.to_string(), CmpOp::Eq, value.clone()))
+            .collect(),
+    );
+    filter_q_config(&filter, columns)
+}
+
+///
+/// build a null-safe inequality condition for a column,
+/// equivalent to `IS DISTINCT FROM` in standard SQL.
+/// Unlike `col != ?`, a NULL column value is considered different from a non-NULL value,
+/// so rows with NULL values are not silently dropped
+/// # Arguments
+/// * `col_name` - the name of the column
+/// * `col_value` - the value to compare with, it can be `Value::Null`
+/// * `columns` - see `columns` of [`filter_q_config`]
+pub fn distinct_from(
+    col_name: &str,
+    col_value: &types::Value,
+    columns: Option<&HashMap<String, types::Value>>,
+) -> Result<(String, Vec<types::Value>)> {
+    let filter = Filter::Cmp(col_name.to_string(), CmpOp::IsNot, col_value.clone());
+    filter_q_config(&filter, columns)
+}
+
+///
+/// the opposite of [`distinct_from`], equivalent to `IS NOT DISTINCT FROM` in standard SQL,
+/// two NULL values are considered equal
+pub fn not_distinct_from(
+    col_name: &str,
+    col_value: &types::Value,
+    columns: Option<&HashMap<String, types::Value>>,
+) -> Result<(String, Vec<types::Value>)> {
+    let filter = Filter::Cmp(col_name.to_string(), CmpOp::Is, col_value.clone());
+    filter_q_config(&filter, columns)
+}
+
+///
+/// The null-ness condition of a column, see [`null_checks`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullCheck {
+    IsNull,
+    IsNotNull,
+}
+
+///
+/// build a condition that checks the null-ness of columns,
+/// the checks are joined by `AND`, and the result can be merged with other conditions by [`merge_q_configs`]
+/// # Arguments
+/// * `checks` - pairs of column names and their expected null-ness, it cannot be empty
+/// * `columns` - if provided, only the columns that are its keys can be checked,
+///   see `columns` of [`filter_q_config`]
+pub fn null_checks(
+    checks: &[(&str, NullCheck)],
+    columns: Option<&HashMap<String, types::Value>>,
+) -> Result<(String, Vec<types::Value>)> {
+    if checks.is_empty() {
+        return Err(anyhow!(
+            "At least 1 column is required for null checks, if you don't need it, specify the `q_config` as None"
+        ));
+    }
+    for (col_name, _) in checks {
+        verify_filter_column(col_name, columns)?;
+    }
+    let clause = checks
+        .iter()
+        .map(|(col_name, check)| match check {
+            NullCheck::IsNull => format!("{} IS NULL", col_name),
+            NullCheck::IsNotNull => format!("{} IS NOT NULL", col_name),
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    Ok((clause, vec![]))
+}
+
+///
+/// build a range condition for a column, i.e. `col BETWEEN ? AND ?`, both bounds are inclusive
+/// # Arguments
+/// * `col_name` - the name of the column
+/// * `range` - `tuple(low, high)`, the bounds must be of the same type,
+///   which is one of integer, real number or text (e.g. dates in ISO 8601 format),
+///   and `low` cannot be greater than `high`
+/// * `columns` - if provided, the column must be one of its keys,
+///   and the bounds must be of the same type as its value, see `columns` of [`filter_q_config`]
+pub fn between(
+    col_name: &str,
+    (low, high): (&types::Value, &types::Value),
+    columns: Option<&HashMap<String, types::Value>>,
+) -> Result<(String, Vec<types::Value>)> {
+    verify_filter_column(col_name, columns)?;
+    if let Some(default_value) = columns.and_then(|columns| columns.get(col_name)) {
+        if !are_same_type(low, default_value) || !are_same_type(high, default_value) {
+            return Err(anyhow!(
+                "The range of '{}' requires bounds of the same type as the column, something like {:?}, but received {:?} and {:?}",
+                col_name,
+                default_value,
+                low,
+                high
+            ));
+        }
+    }
+    let order = match (low, high) {
+        (types::Value::Integer(l), types::Value::Integer(h)) => l.partial_cmp(h),
+        (types::Value::Real(l), types::Value::Real(h)) => l.partial_cmp(h),
+        (types::Value::Text(l), types::Value::Text(h)) => l.partial_cmp(h),
+        _ => None,
+    };
+    match order {
+        None => Err(anyhow!(
+            "The range of '{}' requires 2 bounds of the same integer, real or text type, but received {:?} and {:?}",
+            col_name,
+            low,
+            high
+        )),
+        Some(Ordering::Greater) => Err(anyhow!(
+            "The range of '{}' is inverted, the low bound {:?} is greater than the high bound {:?}",
+            col_name,
+            low,
+            high
+        )),
+        Some(_) => {
+            let clause = format!("{} BETWEEN ? AND ?", col_name);
+            Ok((clause, vec![low.clone(), high.clone()]))
+        }
+    }
+}
+
+///
+/// The comparison operator of [`Filter::Cmp`]
+/// * `Is` / `IsNot` - the null-safe versions of `Eq` / `Ne`, i.e. `col IS ?` / `col IS NOT ?`,
+///   comparing with `Value::Null` matches NULL values instead of nothing,
+///   a single comparison can also be built by [`not_distinct_from`] / [`distinct_from`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    Is,
+    IsNot,
+}
+
+impl CmpOp {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            CmpOp::Eq => "=",
+            CmpOp::Ne => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+            CmpOp::Like => "LIKE",
+            CmpOp::Is => "IS",
+            CmpOp::IsNot => "IS NOT",
+        }
+    }
+}
+
+///
+/// A composable condition that can be compiled into a parameterized clause by [`filter_q_config`]
+/// * `And` - all the conditions must be met
+/// * `Or` - at least 1 of the conditions must be met
+/// * `Cmp` - `tuple(column_name, operator, value)`, compares a column with a value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Cmp(String, CmpOp, types::Value),
+}
+
+///
+/// compile a [`Filter`] into a where clause and its parameters,
+/// every `And` or `Or` group is wrapped in parentheses,
+/// so the result can be safely merged with other conditions by [`merge_q_configs`]
+/// # Arguments
+/// * `filter` - the condition to compile, its groups cannot be empty
+/// * `columns` - if provided, only the columns that are its keys can be compared,
+///   e.g. the defaults of a table, see [`crate::TblRep::get_defaults`]
+pub fn filter_q_config(
+    filter: &Filter,
+    columns: Option<&HashMap<String, types::Value>>,
+) -> Result<(String, Vec<types::Value>)> {
+    match filter {
+        Filter::Cmp(col_name, op, value) => {
+            verify_filter_column(col_name, columns)?;
+            let clause = format!("{} {} ?", col_name, op.as_sql());
+            Ok((clause, vec![value.clone()]))
+        }
+        Filter::And(filters) | Filter::Or(filters) => {
+            let link_word = match filter {
+                Filter::And(_) => "AND",
+                _ => "OR",
+            };
+            if filters.is_empty() {
+                return Err(anyhow!(
+                    "At least 1 condition is required for the filter group '{}'",
+                    link_word
+                ));
+            }
+            let mut clauses = vec![];
+            let mut params = vec![];
+            for filter in filters {
+                let (clause, filter_params) = filter_q_config(filter, columns)?;
+                clauses.push(clause);
+                params.extend(filter_params);
+            }
+            let clause = format!("({})", clauses.join(&format!(" {} ", link_word)));
+            Ok((clause, params))
+        }
+    }
+}
+
+fn verify_filter_column(
+    col_name: &str,
+    columns: Option<&HashMap<String, types::Value>>,
+) -> Result<()> {
+    if columns.is_some_and(|columns| !columns.contains_key(col_name)) {
+        return Err(anyhow!(
+            "The column '{}' cannot be used for filtering",
+            col_name
+        ));
+    }
+    Ok(())
+}
+
 pub fn standardize_q_config(
     q_config: Option<(&str, &[types::Value])>,
     link_word: &str,
@@ -1,8 +1,10 @@
-use super::{sql, verify::verify_table_name};
+use super::{fetch, sql, verify::verify_table_name};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use rusqlite::{params_from_iter, types, Connection};
 
+use std::collections::HashMap;
+
 ///
 /// count all matching records from the table
 /// # Arguments
@@ -25,15 +27,28 @@ pub fn t_all(
     let sql = format!("SELECT COUNT({}) FROM {}", distinct_word, table_name);
     let (where_q_clause, where_q_params) = sql::standardize_q_config(where_q_config, "WHERE")?;
     let sql = format!("{} {}", sql, where_q_clause);
-    let mut stmt = conn.prepare(&sql)?;
+    let mut stmt = conn.prepare_cached(&sql)?;
     let mut rows = stmt.query(params_from_iter(&where_q_params))?;
     let count = rows
         .next()?
-        .ok_or(anyhow::anyhow!("No rows returned from query: {}", sql))?
+        .ok_or(anyhow!("No rows returned from query: {}", sql))?
         .get(0)?;
     Ok(count)
 }
 
+///
+/// count the matching records by the primary key values,
+/// a large list of them is counted in chunks, see [`fetch::f_by_pk`].
+/// The duplicated primary key values are removed first, so no record is counted twice
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `pk_name` - the name of the primary key
+/// * `pk_values` - the values of the primary key
+/// * `distinct_field` - if provided, count distinct non-NULL values in this field,
+///   with more than 1 chunk, the values are collected and deduplicated across the chunks
+///   by [`sql::dedup_values`]
+/// * `where_q_config` - the where clause and the parameters for the where clause
 pub fn t_by_pk(
     conn: &Connection,
     table_name: &str,
@@ -42,15 +57,199 @@ pub fn t_by_pk(
     distinct_field: Option<&str>,
     where_q_config: Option<(&str, &[types::Value])>,
 ) -> Result<i64> {
-    let (pk_query_clause, pk_query_params) = sql::in_them(pk_name, pk_values);
-    let pk_where_refs = (pk_query_clause.as_str(), pk_query_params.as_slice());
-    let (where_q_clause, where_q_params) =
-        sql::merge_q_configs(Some(pk_where_refs), where_q_config, "AND")?;
-    let result = t_all(
-        conn,
+    let chunk_size = fetch::fit_pk_chunk_size(where_q_config);
+    let pk_chunks = sql::in_them_chunks(pk_name, pk_values, chunk_size, false);
+    let mut chunk_q_configs = vec![];
+    for (pk_query_clause, pk_query_params) in &pk_chunks {
+        let pk_where_refs = (pk_query_clause.as_str(), pk_query_params.as_slice());
+        chunk_q_configs.push(sql::merge_q_configs(
+            Some(pk_where_refs),
+            where_q_config,
+            "AND",
+        )?);
+    }
+    match (distinct_field, chunk_q_configs.as_slice()) {
+        (Some(field), [_, _, ..]) => {
+            let mut distinct_values = vec![];
+            for (where_q_clause, where_q_params) in &chunk_q_configs {
+                let rows = fetch::f_all(
+                    conn,
+                    table_name,
+                    Some((where_q_clause.as_str(), where_q_params)),
+                    (true, Some(&[field])),
+                )?;
+                distinct_values.extend(
+                    rows.into_iter()
+                        .filter_map(|mut row| row.remove(field))
+                        .filter(|value| !matches!(value, types::Value::Null)),
+                );
+            }
+            Ok(sql::dedup_values(&distinct_values).len() as i64)
+        }
+        _ => {
+            let mut total = 0;
+            for (where_q_clause, where_q_params) in &chunk_q_configs {
+                total += t_all(
+                    conn,
+                    table_name,
+                    distinct_field,
+                    Some((where_q_clause.as_str(), where_q_params)),
+                )?;
+            }
+            Ok(total)
+        }
+    }
+}
+
+///
+/// count the distinct combinations of the values of multiple columns among the matching records.
+/// Unlike `COUNT(DISTINCT col)`, NULL is counted as a value, so a combination containing NULL
+/// is counted once rather than dropped
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `distinct_fields` - the columns whose value combinations are counted, it cannot be empty
+/// * `where_q_config` - the where clause and the parameters for the where clause
+pub fn t_distinct_combos(
+    conn: &Connection,
+    table_name: &str,
+    distinct_fields: &[&str],
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> Result<i64> {
+    verify_table_name(table_name)?;
+    if distinct_fields.is_empty() {
+        return Err(anyhow!(
+            "(table: {}) At least 1 column is required to count distinct combinations",
+            table_name
+        ));
+    }
+    let (where_q_clause, where_q_params) = sql::standardize_q_config(where_q_config, "WHERE")?;
+    let sql = format!(
+        "SELECT COUNT(*) FROM (SELECT DISTINCT {} FROM {} {})",
+        distinct_fields.join(", "),
         table_name,
-        distinct_field,
-        Some((where_q_clause.as_str(), &where_q_params)),
+        where_q_clause
+    );
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let count = stmt.query_row(params_from_iter(&where_q_params), |row| row.get(0))?;
+    Ok(count)
+}
+
+///
+/// check whether any record matches, it stops at the first match,
+/// so it's cheaper than counting on large tables
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `where_q_config` - the where clause and the parameters for the where clause
+pub fn t_exists(
+    conn: &Connection,
+    table_name: &str,
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> Result<bool> {
+    verify_table_name(table_name)?;
+    let (where_q_clause, where_q_params) = sql::standardize_q_config(where_q_config, "WHERE")?;
+    let sql = format!(
+        "SELECT EXISTS (SELECT 1 FROM {} {})",
+        table_name, where_q_clause
+    );
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let exists = stmt.query_row(params_from_iter(&where_q_params), |row| row.get(0))?;
+    Ok(exists)
+}
+
+///
+/// check whether any record matches by the primary key values,
+/// a large list of them is checked in chunks, see [`fetch::f_by_pk`]
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `pk_name` - the name of the primary key
+/// * `pk_values` - the values of the primary key
+/// * `where_q_config` - the where clause and the parameters for the where clause
+pub fn t_exists_by_pk(
+    conn: &Connection,
+    table_name: &str,
+    pk_name: &str,
+    pk_values: &[types::Value],
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> Result<bool> {
+    let chunk_size = fetch::fit_pk_chunk_size(where_q_config);
+    for (pk_query_clause, pk_query_params) in
+        sql::in_them_chunks(pk_name, pk_values, chunk_size, false)
+    {
+        let pk_where_refs = (pk_query_clause.as_str(), pk_query_params.as_slice());
+        let (where_q_clause, where_q_params) =
+            sql::merge_q_configs(Some(pk_where_refs), where_q_config, "AND")?;
+        if t_exists(
+            conn,
+            table_name,
+            Some((where_q_clause.as_str(), &where_q_params)),
+        )? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+///
+/// count the matching records by equal-width ranges of a numeric column
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `table_name` - the name of the table
+/// * `col_name` - the name of the numeric column
+/// * `range_config` - `tuple(min, max, bucket_count)`,
+///   records whose values are outside of `[min, max]` are not counted,
+///   values equal to `max` are counted in the last bucket
+/// * `where_q_config` - the where clause and the parameters for the where clause
+/// # Returns
+/// * `HashMap<bucket_index, count>` - buckets without any records are absent
+pub fn t_histogram(
+    conn: &Connection,
+    table_name: &str,
+    col_name: &str,
+    (min, max, bucket_count): (f64, f64, u32),
+    where_q_config: Option<(&str, &[types::Value])>,
+) -> Result<HashMap<i64, i64>> {
+    verify_table_name(table_name)?;
+    if bucket_count == 0 || min >= max {
+        return Err(anyhow!(
+            "(table: {}) The histogram of '{}' requires at least 1 bucket and min < max, but received min: {}, max: {}, buckets: {}",
+            table_name,
+            col_name,
+            min,
+            max,
+            bucket_count
+        ));
+    }
+    let width = (max - min) / bucket_count as f64;
+    let range_clause = format!("{} >= ? AND {} <= ?", col_name, col_name);
+    let range_params = [types::Value::Real(min), types::Value::Real(max)];
+    sql::standardize_q_config(where_q_config, "")?;
+    // the user's clause is grouped, so an `OR` in it cannot escape the range
+    let where_q_config = where_q_config.map(|(clause, params)| (format!("({})", clause), params));
+    let (where_clause, where_params) = sql::merge_q_configs(
+        Some((range_clause.as_str(), &range_params)),
+        where_q_config
+            .as_ref()
+            .map(|(clause, params)| (clause.as_str(), *params)),
+        "AND",
     )?;
+    let sql = format!(
+        "SELECT MIN(CAST(({} - ?) / ? AS INTEGER), ?) AS bucket, COUNT(*) FROM {} WHERE {} GROUP BY bucket",
+        col_name, table_name, where_clause
+    );
+    let bucket_params = vec![
+        types::Value::Real(min),
+        types::Value::Real(width),
+        types::Value::Integer(bucket_count as i64 - 1),
+    ];
+    let params = [bucket_params, where_params].concat();
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let mut rows = stmt.query(params_from_iter(&params))?;
+    let mut result = HashMap::new();
+    while let Some(row) = rows.next()? {
+        result.insert(row.get(0)?, row.get(1)?);
+    }
     Ok(result)
 }
@@ -1,6 +1,9 @@
 use super::{
-    sql,
-    verify::{verify_required_fields_for_write_ops, verify_table_name, verify_values_required},
+    atomic::in_savepoint,
+    fetch, sql,
+    verify::{
+        verify_required_fields_for_write_ops_with, verify_table_name, verify_values_required,
+    },
 };
 
 use rusqlite::{params_from_iter, types, Connection};
@@ -14,19 +17,29 @@ use std::collections::{HashMap, HashSet};
 /// * `table_name` - the name of the table
 /// * `input` - the new values for the record
 /// * `where_q_config` - the where clause and the parameters for the where clause,
-///                      to reduce the chance of unwanted updates,
-///                      this is not an Option and cannot contain empty clause
-/// * `verification_options` - the options for verification, if None, no verification is performed
+///   to reduce the chance of unwanted updates,
+///   this is not an Option and cannot contain empty clause
+/// * `verification_options` - `tuple(defaults, required_fields, allow_null)`,
+///   if None, no verification is performed,
+///   see `allow_null` of [`verify_required_fields_for_write_ops_with`]
+/// # Returns
+/// * `Ok(affected_rows)` - the number of records that are updated
 pub fn u_all(
     conn: &Connection,
     table_name: &str,
     input: &HashMap<String, types::Value>,
     where_q_config: (&str, &[types::Value]),
-    verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>)>,
-) -> anyhow::Result<()> {
+    verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
+) -> anyhow::Result<usize> {
     verify_table_name(table_name)?;
-    if let Some((defaults, required_fields)) = verification_options {
-        verify_required_fields_for_write_ops(input, table_name, required_fields, defaults, false)?;
+    if let Some((defaults, required_fields, allow_null)) = verification_options {
+        verify_required_fields_for_write_ops_with(
+            input,
+            table_name,
+            required_fields,
+            defaults,
+            (false, allow_null),
+        )?;
     }
     let mut set_clause = vec![];
     let mut set_params = vec![];
@@ -43,8 +56,8 @@ pub fn u_all(
         where_clause,
     );
     let mut stmt = conn.prepare(&sql)?;
-    stmt.execute(params_from_iter(&params))?;
-    Ok(())
+    let affected_rows = stmt.execute(params_from_iter(&params))?;
+    Ok(affected_rows)
 }
 
 ///
@@ -58,8 +71,11 @@ pub fn u_all(
 /// * `pk_values` - records to be updated represented by their primary key values
 /// * `input` - the new values for the record
 /// * `where_q_config` - the where clause and the parameters for the where clause
+/// * `verification_options` - see `verification_options` of [`u_all`]
+///
+/// A large list of primary key values is updated in chunks within a savepoint, see [`super::fetch::f_by_pk`]
 /// # Returns
-/// * `Ok(())` - if the record is updated successfully
+/// * `Ok(affected_rows)` - the number of records that are updated
 pub fn u_by_pk(
     conn: &Connection,
     table_name: &str,
@@ -67,18 +83,26 @@ pub fn u_by_pk(
     pk_values: &[types::Value],
     input: &HashMap<String, types::Value>,
     where_q_config: Option<(&str, &[types::Value])>,
-    verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>)>,
-) -> anyhow::Result<()> {
+    verification_options: Option<(&HashMap<String, types::Value>, &HashSet<String>, bool)>,
+) -> anyhow::Result<usize> {
     verify_values_required(pk_values, table_name, pk_name)?;
-    let (pk_where_clause, pk_where_params) = sql::in_them(pk_name, pk_values);
-    let pk_where_refs = (pk_where_clause.as_str(), pk_where_params.as_slice());
-    let where_q_config = sql::merge_q_configs(Some(pk_where_refs), where_q_config, "AND")?;
-    u_all(
-        conn,
-        table_name,
-        input,
-        (where_q_config.0.as_str(), &where_q_config.1),
-        verification_options,
-    )?;
-    Ok(())
+    let where_param_count = where_q_config.map_or(0, |(_, params)| params.len());
+    let chunk_size = sql::fit_chunk_size(fetch::PK_CHUNK_SIZE, input.len() + where_param_count);
+    let pk_chunks = sql::in_them_chunks(pk_name, pk_values, chunk_size, false);
+    in_savepoint(conn, "u_by_pk", || {
+        let mut affected_rows = 0;
+        for (i, (pk_where_clause, pk_where_params)) in pk_chunks.iter().enumerate() {
+            let pk_where_refs = (pk_where_clause.as_str(), pk_where_params.as_slice());
+            let where_q_config = sql::merge_q_configs(Some(pk_where_refs), where_q_config, "AND")?;
+            affected_rows += u_all(
+                conn,
+                table_name,
+                input,
+                (where_q_config.0.as_str(), &where_q_config.1),
+                // the input is the same for all chunks, so it's verified only once
+                verification_options.filter(|_| i == 0),
+            )?;
+        }
+        Ok(affected_rows)
+    })
 }
@@ -1,7 +1,6 @@
-use super::verify;
-
 use anyhow::{anyhow, Result};
 use rusqlite::types;
+use serde::Serialize;
 
 use std::collections::{HashMap, HashSet};
 
@@ -31,6 +30,16 @@ pub fn are_same_type(val1: &types::Value, val2: &types::Value) -> bool {
     )
 }
 
+fn is_acceptable_type(
+    (key, input_value): (&str, &types::Value),
+    default_value: &types::Value,
+    null_guarded_fields: Option<&HashSet<String>>,
+) -> bool {
+    let is_nullable = null_guarded_fields.is_some_and(|fields| !fields.contains(key));
+    (is_nullable && matches!(input_value, types::Value::Null))
+        || are_same_type(input_value, default_value)
+}
+
 ///
 /// Verify the basic write operations for the table
 ///
@@ -42,16 +51,28 @@ pub fn are_same_type(val1: &types::Value, val2: &types::Value) -> bool {
 ///   even if they are not specified in the input
 /// # Arguments
 /// * `input` - the input for the operation, it
-///             - cannot contains keys that are not defined in the `defaults`
-///             - must contains values that are of the same type as the ones in the `defaults`
+///   - cannot contains keys that are not defined in the `defaults`
+///   - must contains values that are of the same type as the ones in the `defaults`
 /// * `table_name` - the name of the table
 /// * `defaults` - the default values for `all` the columns that will be operated on
-///                (including the ones that are not provided in the input but are defined by the operation)
+///   (including the ones that are not provided in the input but are defined by the operation)
 ///
 pub fn verify_basic_write_ops(
     input: &HashMap<String, types::Value>,
     table_name: &str,
     defaults: &HashMap<String, types::Value>,
+) -> Result<()> {
+    verify_write_input_types(input, table_name, defaults, None)
+}
+
+///
+/// the same as [`verify_basic_write_ops`], except that when `null_guarded_fields` is specified,
+/// `Value::Null` is accepted for the columns that are not in it
+fn verify_write_input_types(
+    input: &HashMap<String, types::Value>,
+    table_name: &str,
+    defaults: &HashMap<String, types::Value>,
+    null_guarded_fields: Option<&HashSet<String>>,
 ) -> Result<()> {
     if input.keys().len() == 0 {
         return Err(anyhow!("(table: {}) The input has no items", table_name));
@@ -61,7 +82,7 @@ pub fn verify_basic_write_ops(
             Some(v) => v,
             None => return true,
         };
-        !verify::are_same_type(input_value, default_value)
+        !is_acceptable_type((key, input_value), default_value, null_guarded_fields)
     });
 
     if let Some(mismatch) = first_mismatch {
@@ -97,6 +118,29 @@ pub fn is_empty(val1: &types::Value) -> bool {
     }
 }
 
+///
+/// turn the empty values of the fields that are not required into `Value::Null`,
+/// e.g. `""` sent by a form for a cleared optional field, so that `IS NULL` conditions can find them.
+/// Empty values of required fields are kept, so they are still rejected by the verification
+/// # Arguments
+/// * `input` - the input of a write operation
+/// * `required_fields` - the names of the fields that are required
+pub fn empty_as_null(
+    input: &HashMap<String, types::Value>,
+    required_fields: &HashSet<String>,
+) -> HashMap<String, types::Value> {
+    input
+        .iter()
+        .map(|(key, value)| {
+            if !required_fields.contains(key) && is_empty(value) {
+                (key.clone(), types::Value::Null)
+            } else {
+                (key.clone(), value.clone())
+            }
+        })
+        .collect()
+}
+
 pub fn is_violating_required_rule(input: &HashMap<String, types::Value>, key: &str) -> bool {
     match input.get(key) {
         Some(val) => is_empty(val),
@@ -111,10 +155,13 @@ pub fn is_violating_required_rule(input: &HashMap<String, types::Value>, key: &s
 /// * `table_name` - see `table_name` of [`verify_basic_write_ops`]
 /// * `required_fields` - the names of fields that cannot be left unspecified
 /// * `all_required` - whether all required fields are needed, if false,
-///                    only the required fields that are present in the input are checked,
-///                    for example,
-///                    - `false` is used for the update operation
-///                    - `true` is used for the insert operation
+///   only the required fields that are present in the input are checked,
+///   for example,
+///   - `false` is used for the update operation
+///   - `true` is used for the insert operation
+///
+/// `Value::Null` is rejected as a type mismatch like any other column,
+/// see [`verify_required_fields_for_write_ops_with`] to accept it
 pub fn verify_required_fields_for_write_ops(
     input: &HashMap<String, types::Value>,
     table_name: &str,
@@ -122,7 +169,29 @@ pub fn verify_required_fields_for_write_ops(
     defaults: &HashMap<String, types::Value>,
     all_required: bool,
 ) -> Result<()> {
-    verify_basic_write_ops(input, table_name, defaults)?;
+    verify_required_fields_for_write_ops_with(
+        input,
+        table_name,
+        required_fields,
+        defaults,
+        (all_required, false),
+    )
+}
+
+/// The same as [`verify_required_fields_for_write_ops`], but `Value::Null` can be accepted
+/// # Arguments
+/// * `all_required` - see `all_required` of [`verify_required_fields_for_write_ops`]
+/// * `allow_null` - whether `Value::Null` is accepted to clear the columns that are not required,
+///   if false, `Value::Null` is rejected as a type mismatch like any other column
+pub fn verify_required_fields_for_write_ops_with(
+    input: &HashMap<String, types::Value>,
+    table_name: &str,
+    required_fields: &HashSet<String>,
+    defaults: &HashMap<String, types::Value>,
+    (all_required, allow_null): (bool, bool),
+) -> Result<()> {
+    let null_guarded_fields = allow_null.then_some(required_fields);
+    verify_write_input_types(input, table_name, defaults, null_guarded_fields)?;
     let first_none = if all_required {
         required_fields
             .iter()
@@ -142,6 +211,91 @@ pub fn verify_required_fields_for_write_ops(
     Ok(())
 }
 
+///
+/// A report of all the problems found in the input of a write operation,
+/// unlike the verification functions, it doesn't stop at the first problem.
+/// See also [`report_write_input_issues`]
+/// * `unknown_keys` - the keys that are not defined in the defaults
+/// * `type_mismatches` - the keys whose values are of different types from their default values
+/// * `missing_required` - the required fields that are absent or empty
+/// * `is_empty_input` - whether the input has no items, which is rejected by the verification
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WriteInputReport {
+    pub unknown_keys: Vec<String>,
+    pub type_mismatches: Vec<String>,
+    pub missing_required: Vec<String>,
+    pub is_empty_input: bool,
+}
+
+impl WriteInputReport {
+    /// Returns true if no problem is found
+    pub fn is_clean(&self) -> bool {
+        !self.is_empty_input
+            && self.unknown_keys.is_empty()
+            && self.type_mismatches.is_empty()
+            && self.missing_required.is_empty()
+    }
+}
+
+///
+/// Collect all the problems of the input for a write operation at once,
+/// it checks the same rules as [`verify_required_fields_for_write_ops`]
+/// # Arguments
+/// * `input` - see `input` of [`verify_basic_write_ops`]
+/// * `required_fields` - see `required_fields` of [`verify_required_fields_for_write_ops`]
+/// * `defaults` - see `defaults` of [`verify_basic_write_ops`]
+/// * `all_required` - see `all_required` of [`verify_required_fields_for_write_ops`]
+/// # Returns
+/// * `WriteInputReport` - the column names in each group are sorted alphabetically
+pub fn report_write_input_issues(
+    input: &HashMap<String, types::Value>,
+    required_fields: &HashSet<String>,
+    defaults: &HashMap<String, types::Value>,
+    all_required: bool,
+) -> WriteInputReport {
+    report_write_input_issues_with(input, required_fields, defaults, (all_required, false))
+}
+
+/// The same as [`report_write_input_issues`], but `Value::Null` can be accepted,
+/// it checks the same rules as [`verify_required_fields_for_write_ops_with`]
+/// # Arguments
+/// * `all_required` - see `all_required` of [`verify_required_fields_for_write_ops`]
+/// * `allow_null` - see `allow_null` of [`verify_required_fields_for_write_ops_with`]
+pub fn report_write_input_issues_with(
+    input: &HashMap<String, types::Value>,
+    required_fields: &HashSet<String>,
+    defaults: &HashMap<String, types::Value>,
+    (all_required, allow_null): (bool, bool),
+) -> WriteInputReport {
+    let mut report = WriteInputReport {
+        is_empty_input: input.is_empty(),
+        ..Default::default()
+    };
+    let null_guarded_fields = allow_null.then_some(required_fields);
+    for (key, input_value) in input {
+        match defaults.get(key) {
+            None => report.unknown_keys.push(key.clone()),
+            Some(default_value)
+                if !is_acceptable_type((key, input_value), default_value, null_guarded_fields) =>
+            {
+                report.type_mismatches.push(key.clone())
+            }
+            _ => {}
+        }
+    }
+    report.missing_required = required_fields
+        .iter()
+        .filter(|field| {
+            (all_required || input.contains_key(*field)) && is_violating_required_rule(input, field)
+        })
+        .cloned()
+        .collect();
+    report.unknown_keys.sort();
+    report.type_mismatches.sort();
+    report.missing_required.sort();
+    report
+}
+
 /// Verify the values for the operation of the table
 /// * The main purpose of this function is to avoid some operations such as updates or deletes to accidentally modify or delete all rows
 /// # Arguments
@@ -175,10 +329,42 @@ pub fn verify_values_required(
     Ok(())
 }
 
+///
+/// Verify the chunk size of the values split by [`super::sql::in_them_chunks`]
+/// # Arguments
+/// * `table_name` - the name of the table
+/// * `chunk_size` - the maximum number of values in a single statement
+/// * `other_param_count` - the number of the other parameters bound in the same statement
+/// # Returns
+/// * `Err` - if the chunk size is 0, or a chunk and the other parameters together
+///   exceed [`super::sql::MAX_BOUND_PARAMS`]
+pub fn verify_chunk_size(
+    table_name: &str,
+    chunk_size: usize,
+    other_param_count: usize,
+) -> Result<()> {
+    if chunk_size == 0 {
+        return Err(anyhow!(
+            "(table: {}) The chunk size of primary key values must be positive",
+            table_name
+        ));
+    }
+    if chunk_size + other_param_count > super::sql::MAX_BOUND_PARAMS {
+        return Err(anyhow!(
+            "(table: {}) The chunk size {} and the other {} parameters exceed the limit of {} bound parameters",
+            table_name,
+            chunk_size,
+            other_param_count,
+            super::sql::MAX_BOUND_PARAMS
+        ));
+    }
+    Ok(())
+}
+
 ///
 /// Make a record based on an input,
 /// if a field is absent in the input, the default value is used if available
-fn defaults_if_absent(
+pub(crate) fn defaults_if_absent(
     defaults: &HashMap<String, types::Value>,
     input: &HashMap<String, types::Value>,
 ) -> HashMap<String, types::Value> {
@@ -1,4 +1,4 @@
-use crate::crud::{create, del, fetch, total, update, verify};
+use crate::crud::{atomic, create, del, fetch, shift, sql, total, update, verify};
 
 // in case of version conflicts, these crates below are published
 pub use rusqlite::{types, Connection};
@@ -6,9 +6,35 @@ pub use serde;
 pub use serde_json;
 
 use anyhow::{anyhow, Result};
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+///
+/// The columns of a table grouped by the kinds of queries they can take part in,
+/// so that generic UIs only offer controls that produce valid queries
+/// * `sortable` - columns that can be used for ordering, i.e. all non-blob columns
+/// * `filterable` - columns that can be used in conditions, i.e. all non-blob columns
+/// * `searchable` - columns that can be used for keyword matching, i.e. text columns
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct QueryableColumns {
+    pub sortable: Vec<String>,
+    pub filterable: Vec<String>,
+    pub searchable: Vec<String>,
+}
+
+///
+/// InsertVerification is a tuple of (defaults, required_fields, default_if_absent)
+type InsertVerification<'b> = (&'b HashMap<String, types::Value>, &'b HashSet<String>, bool);
+
+/// InsertArgs is a tuple of (input, verification_options) to pass to the insert functions
+type InsertArgs<'b> = (
+    Cow<'b, HashMap<String, types::Value>>,
+    Option<InsertVerification<'b>>,
+);
 
 ///
 /// The TblRep is a representation of a table in the database
@@ -18,6 +44,10 @@ pub struct TblRep {
     pk_name: String,
     defaults: HashMap<String, types::Value>,
     required_fields: HashSet<String>,
+    allow_null: bool,
+    empty_as_null: bool,
+    with_soft_deleted: bool,
+    require_match: bool,
 }
 
 impl TblRep {
@@ -63,6 +93,10 @@ impl TblRep {
                 .iter()
                 .map(|(k, v)| (k.to_string(), v.clone()))
                 .collect(),
+            allow_null: false,
+            empty_as_null: false,
+            with_soft_deleted: false,
+            require_match: false,
         })
     }
 
@@ -82,13 +116,272 @@ impl TblRep {
         &self.defaults
     }
 
+    /// Returns whether updates accept `Value::Null` for the fields that are not required,
+    /// see [`TblRep::set_allow_null`]
+    pub fn get_allow_null(&self) -> bool {
+        self.allow_null
+    }
+
+    ///
+    /// set whether the updates of this table accept `Value::Null` to clear the fields that are not required,
+    /// it's off by default, so `Value::Null` is rejected as a type mismatch.
+    /// Required fields never accept `Value::Null`.
+    /// See also `allow_null` of [`verify::verify_required_fields_for_write_ops_with`]
+    pub fn set_allow_null(&mut self, allow_null: bool) {
+        self.allow_null = allow_null;
+    }
+
+    /// Returns whether empty values of the fields that are not required are written as NULL,
+    /// see [`TblRep::set_empty_as_null`]
+    pub fn get_empty_as_null(&self) -> bool {
+        self.empty_as_null
+    }
+
+    ///
+    /// set whether the write operations of this table turn the empty text and blob values
+    /// of the fields that are not required into NULL, it's off by default.
+    /// Empty values of required fields are still rejected.
+    /// [`TblRep::upsert`] is not affected, because its absent fields are filled only when inserting.
+    /// See also [`verify::empty_as_null`]
+    pub fn set_empty_as_null(&mut self, empty_as_null: bool) {
+        self.empty_as_null = empty_as_null;
+    }
+
+    /// Returns whether the reads of this table include soft-deleted records,
+    /// see [`TblRep::set_with_soft_deleted`]
+    pub fn get_with_soft_deleted(&self) -> bool {
+        self.with_soft_deleted
+    }
+
+    ///
+    /// set whether the reads of this table (listing, counting, checking existence, etc.) include soft-deleted records.
+    /// It's off by default, so when the defaults define [`del::SOFT_DEL_COL`] with an empty text default,
+    /// the reads only see the records that are not soft-deleted, see [`del::not_soft_deleted`].
+    /// Tables without such a column are not affected
+    pub fn set_with_soft_deleted(&mut self, with_soft_deleted: bool) {
+        self.with_soft_deleted = with_soft_deleted;
+    }
+
+    /// Returns whether the updates of this table fail when no record matches,
+    /// see [`TblRep::set_require_match`]
+    pub fn get_require_match(&self) -> bool {
+        self.require_match
+    }
+
+    ///
+    /// set whether [`TblRep::upd_by_pk`], [`TblRep::upd_each`] and [`TblRep::upd_by_filter`]
+    /// return an error when no record matches, e.g. to respond with "not found".
+    /// It's off by default, so an update that matches nothing succeeds with 0 affected rows
+    pub fn set_require_match(&mut self, require_match: bool) {
+        self.require_match = require_match;
+    }
+
+    ///
+    /// check the affected rows of an update against [`TblRep::set_require_match`]
+    fn verify_update_matched(&self, affected_rows: usize) -> Result<usize> {
+        if self.require_match && affected_rows == 0 {
+            return Err(anyhow!(
+                "(table: {}) No record matches the update",
+                self.name
+            ));
+        }
+        Ok(affected_rows)
+    }
+
+    ///
+    /// whether the table defines [`del::SOFT_DEL_COL`] with an empty text default,
+    /// which is the only shape that [`del::not_soft_deleted`] treats as "not deleted",
+    /// a column of any other type is left alone, so its records never vanish from the reads
+    fn is_soft_deletable(&self) -> bool {
+        matches!(
+            self.defaults.get(del::SOFT_DEL_COL),
+            Some(types::Value::Text(text)) if text.is_empty()
+        )
+    }
+
+    ///
+    /// run a read with the where clause that excludes the soft-deleted records when needed,
+    /// see [`TblRep::set_with_soft_deleted`]
+    fn with_read_q_config<T>(
+        &self,
+        where_q_config: Option<(&str, &[types::Value])>,
+        read: impl FnOnce(Option<(&str, &[types::Value])>) -> Result<T>,
+    ) -> Result<T> {
+        if self.with_soft_deleted || !self.is_soft_deletable() {
+            return read(where_q_config);
+        }
+        let (alive_clause, alive_params) = del::not_soft_deleted();
+        let (clause, params) = match where_q_config {
+            Some((where_clause, where_params)) => {
+                sql::standardize_q_config(where_q_config, "")?;
+                let where_clause = format!("({})", where_clause);
+                sql::merge_q_configs(
+                    Some((&alive_clause, &alive_params)),
+                    Some((&where_clause, where_params)),
+                    "AND",
+                )?
+            }
+            None => (alive_clause, alive_params),
+        };
+        read(Some((&clause, &params)))
+    }
+
+    ///
+    /// the input and the verification options to pass to the insert functions,
+    /// with `empty_as_null`, the input is verified first, because inserts don't accept NULL values
+    fn insert_args<'b>(
+        &'b self,
+        input: &'b HashMap<String, types::Value>,
+        verification_options: InsertVerification<'b>,
+    ) -> Result<InsertArgs<'b>> {
+        if !self.empty_as_null {
+            return Ok((Cow::Borrowed(input), Some(verification_options)));
+        }
+        let verified_input =
+            verify::get_verified_insert_inputs(&self.name, input, Some(verification_options))?;
+        let (_, required_fields, _) = verification_options;
+        let input = verify::empty_as_null(&verified_input, required_fields);
+        Ok((Cow::Owned(input), None))
+    }
+
+    ///
+    /// `Value::Null` is also accepted with `empty_as_null`, because that's what the empty values become
+    fn update_allows_null(&self) -> bool {
+        self.allow_null || self.empty_as_null
+    }
+
+    fn update_verification(&self) -> (&HashMap<String, types::Value>, &HashSet<String>, bool) {
+        (
+            &self.defaults,
+            &self.required_fields,
+            self.update_allows_null(),
+        )
+    }
+
+    fn update_input<'b>(
+        &self,
+        input: &'b HashMap<String, types::Value>,
+    ) -> Cow<'b, HashMap<String, types::Value>> {
+        if self.empty_as_null {
+            Cow::Owned(verify::empty_as_null(input, &self.required_fields))
+        } else {
+            Cow::Borrowed(input)
+        }
+    }
+
+    ///
+    /// compile a [`sql::Filter`] into a where clause and its parameters for this table,
+    /// only the columns defined in the defaults can be compared.
+    /// See also [`sql::filter_q_config`]
+    /// # Arguments
+    /// * `filter` - the condition to compile
+    pub fn filter_q_config(&self, filter: &sql::Filter) -> Result<(String, Vec<types::Value>)> {
+        sql::filter_q_config(filter, Some(&self.defaults))
+            .map_err(|err| anyhow!("(table: {}) {}", self.name, err))
+    }
+
+    ///
+    /// build a range condition for a column of this table,
+    /// the column must be defined in the defaults, and the bounds must be of the same type as its default value.
+    /// See also [`sql::between`]
+    /// # Arguments
+    /// * `col_name` - the name of the column
+    /// * `range` - `tuple(low, high)`, both bounds are inclusive
+    pub fn between_q_config(
+        &self,
+        col_name: &str,
+        range: (&types::Value, &types::Value),
+    ) -> Result<(String, Vec<types::Value>)> {
+        sql::between(col_name, range, Some(&self.defaults))
+            .map_err(|err| anyhow!("(table: {}) {}", self.name, err))
+    }
+
+    ///
+    /// build a condition that checks the null-ness of columns of this table,
+    /// only the columns defined in the defaults can be checked.
+    /// See also [`sql::null_checks`]
+    /// # Arguments
+    /// * `checks` - pairs of column names and their expected null-ness
+    pub fn null_checks_q_config(
+        &self,
+        checks: &[(&str, sql::NullCheck)],
+    ) -> Result<(String, Vec<types::Value>)> {
+        sql::null_checks(checks, Some(&self.defaults))
+            .map_err(|err| anyhow!("(table: {}) {}", self.name, err))
+    }
+
+    ///
+    /// classify the columns of the table by the data types of their default values.
+    /// See also [`QueryableColumns`]
+    /// # Returns
+    /// * `QueryableColumns` - the column names in each group are sorted alphabetically
+    pub fn queryable_columns(&self) -> QueryableColumns {
+        let mut columns = self.defaults.iter().collect::<Vec<_>>();
+        columns.sort_by_key(|(name, _)| *name);
+        let mut queryable = QueryableColumns::default();
+        for (name, default_value) in columns {
+            match default_value {
+                types::Value::Blob(_) => continue,
+                types::Value::Text(_) => queryable.searchable.push(name.to_string()),
+                _ => {}
+            }
+            queryable.sortable.push(name.to_string());
+            queryable.filterable.push(name.to_string());
+        }
+        queryable
+    }
+
+    ///
+    /// generate a TypeScript interface for the records of the table,
+    /// e.g. to share the record shapes with a web frontend.
+    /// * the interface is named after the table in PascalCase, e.g. `rel_album_song` -> `RelAlbumSong`
+    /// * the required fields are non-optional, other fields are optional
+    /// * integers and real numbers are `number`, texts are `string`,
+    ///   blobs are `number[]`, the same as [`shift::val_to_json`] renders them
+    /// * the fields are sorted alphabetically
+    pub fn to_typescript(&self) -> String {
+        let interface_name = self
+            .name
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                    None => String::new(),
+                }
+            })
+            .collect::<String>();
+        let mut columns = self.defaults.iter().collect::<Vec<_>>();
+        columns.sort_by_key(|(name, _)| *name);
+        let fields = columns
+            .into_iter()
+            .map(|(name, default_value)| {
+                let ts_type = match default_value {
+                    types::Value::Integer(_) | types::Value::Real(_) => "number",
+                    types::Value::Text(_) => "string",
+                    types::Value::Blob(_) => "number[]",
+                    types::Value::Null => "null",
+                };
+                let optional_mark = if self.required_fields.contains(name) {
+                    ""
+                } else {
+                    "?"
+                };
+                format!("  {}{}: {};\n", name, optional_mark, ts_type)
+            })
+            .collect::<String>();
+        format!("export interface {} {{\n{}}}\n", interface_name, fields)
+    }
+
     pub fn count(
         &self,
         conn: &Connection,
         distinct_field: Option<&str>,
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> Result<i64> {
-        total::t_all(conn, &self.name, distinct_field, where_q_config)
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            total::t_all(conn, &self.name, distinct_field, where_q_config)
+        })
     }
 
     pub fn count_by_pk(
@@ -98,14 +391,102 @@ impl TblRep {
         distinct_field: Option<&str>,
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> Result<i64> {
-        total::t_by_pk(
-            conn,
-            &self.name,
-            &self.pk_name,
-            pk_values,
-            distinct_field,
-            where_q_config,
-        )
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            total::t_by_pk(
+                conn,
+                &self.name,
+                &self.pk_name,
+                pk_values,
+                distinct_field,
+                where_q_config,
+            )
+        })
+    }
+
+    ///
+    /// count the distinct combinations of the values of multiple columns.
+    /// See also [`total::t_distinct_combos`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `distinct_fields` - the columns whose value combinations are counted,
+    ///   they must be defined in the defaults
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    pub fn count_distinct_combos(
+        &self,
+        conn: &Connection,
+        distinct_fields: &[&str],
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<i64> {
+        if let Some(unknown) = distinct_fields
+            .iter()
+            .find(|field| !self.defaults.contains_key(**field))
+        {
+            return Err(anyhow!(
+                "(table: {}) The column '{}' does not exist",
+                self.name,
+                unknown
+            ));
+        }
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            total::t_distinct_combos(conn, &self.name, distinct_fields, where_q_config)
+        })
+    }
+
+    ///
+    /// check whether any record of the table matches.
+    /// See also [`total::t_exists`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    pub fn exists(
+        &self,
+        conn: &Connection,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<bool> {
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            total::t_exists(conn, &self.name, where_q_config)
+        })
+    }
+
+    pub fn exists_by_pk(
+        &self,
+        conn: &Connection,
+        pk_values: &[types::Value],
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<bool> {
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            total::t_exists_by_pk(conn, &self.name, &self.pk_name, pk_values, where_q_config)
+        })
+    }
+
+    ///
+    /// count the matching records by equal-width ranges of a numeric column.
+    /// See also [`total::t_histogram`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `col_name` - the name of the column, its default value must be an integer or a real number
+    /// * `range_config` - `tuple(min, max, bucket_count)`
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    pub fn histogram(
+        &self,
+        conn: &Connection,
+        col_name: &str,
+        range_config: (f64, f64, u32),
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<HashMap<i64, i64>> {
+        if !matches!(
+            self.defaults.get(col_name),
+            Some(types::Value::Integer(_) | types::Value::Real(_))
+        ) {
+            return Err(anyhow!(
+                "(table: {}) The column '{}' is not a numeric column",
+                self.name,
+                col_name
+            ));
+        }
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            total::t_histogram(conn, &self.name, col_name, range_config, where_q_config)
+        })
     }
 
     ///
@@ -124,7 +505,29 @@ impl TblRep {
         where_q_config: Option<(&str, &[types::Value])>,
         display_config: (bool, Option<&[&str]>),
     ) -> Result<Vec<HashMap<String, types::Value>>> {
-        fetch::f_all(conn, &self.name, where_q_config, display_config)
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_all(conn, &self.name, where_q_config, display_config)
+        })
+    }
+
+    ///
+    /// fetch all matching records from the table, with the columns kept in order.
+    /// See also [`fetch::f_all_ordered`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    /// * `display_config` - whether to use DISTINCT, and the fields to be displayed
+    /// # Returns
+    /// * `Ok(Vec<row_records>)` - each record is a Vec of `(field_name, value)` pairs
+    pub fn list_ordered(
+        &self,
+        conn: &Connection,
+        where_q_config: Option<(&str, &[types::Value])>,
+        display_config: (bool, Option<&[&str]>),
+    ) -> Result<Vec<Vec<(String, types::Value)>>> {
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_all_ordered(conn, &self.name, where_q_config, display_config)
+        })
     }
 
     ///
@@ -141,7 +544,135 @@ impl TblRep {
         where_q_config: Option<(&str, &[types::Value])>,
         display_config: (bool, Option<&[&str]>),
     ) -> Result<Vec<T>> {
-        fetch::f_all_as(conn, &self.name, where_q_config, display_config)
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_all_as(conn, &self.name, where_q_config, display_config)
+        })
+    }
+
+    ///
+    /// the same as [`TblRep::list_as`], but the blobs are converted with the given encoding.
+    /// See also [`shift::list_as_with`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    /// * `display_config` - `tuple(is_distinct, display_fields)`
+    /// * `blob_encoding` - how the blob values are converted, see [`shift::BlobEncoding`]
+    pub fn list_as_with<T: DeserializeOwned>(
+        &self,
+        conn: &Connection,
+        where_q_config: Option<(&str, &[types::Value])>,
+        display_config: (bool, Option<&[&str]>),
+        blob_encoding: shift::BlobEncoding,
+    ) -> Result<Vec<T>> {
+        let rows = self.list(conn, where_q_config, display_config)?;
+        shift::list_as_with(&self.name, &rows, blob_encoding)
+    }
+
+    ///
+    /// fetch all matching records from the table as JSON objects, e.g. to be sent as a response.
+    /// See also [`shift::rows_to_json_with`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    /// * `display_config` - `tuple(is_distinct, display_fields)`
+    /// * `blob_encoding` - how the blob values are rendered, see [`shift::BlobEncoding`]
+    pub fn list_json(
+        &self,
+        conn: &Connection,
+        where_q_config: Option<(&str, &[types::Value])>,
+        display_config: (bool, Option<&[&str]>),
+        blob_encoding: shift::BlobEncoding,
+    ) -> Result<Vec<serde_json::Value>> {
+        let rows = self.list(conn, where_q_config, display_config)?;
+        shift::rows_to_json_with(&rows, blob_encoding)
+    }
+
+    ///
+    /// fetch a page of records that come after a cursor in the order of a column.
+    /// See also [`fetch::f_after`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `page_config` - `tuple(order_column, cursor, is_descending, page_size)`,
+    ///   the order column must be defined in the defaults and cannot be a blob column
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    /// # Returns
+    /// * `tuple(records, next_cursor)` - `next_cursor` is `None` if there are no more records
+    pub fn list_after(
+        &self,
+        conn: &Connection,
+        page_config: (&str, Option<&types::Value>, bool, u32),
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<fetch::KeysetPage> {
+        let (order_col, ..) = page_config;
+        if !self
+            .queryable_columns()
+            .sortable
+            .iter()
+            .any(|col| col == order_col)
+        {
+            return Err(anyhow!(
+                "(table: {}) The column '{}' cannot be used for ordering",
+                self.name,
+                order_col
+            ));
+        }
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_after(conn, &self.name, page_config, where_q_config, None)
+        })
+    }
+
+    ///
+    /// visit all matching records from the table one at a time without collecting them.
+    /// See also [`fetch::f_each`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    /// * `display_config` - whether to use DISTINCT, and the fields to be displayed
+    /// * `on_record` - called with each record, an error stops the iteration and is returned
+    pub fn each(
+        &self,
+        conn: &Connection,
+        where_q_config: Option<(&str, &[types::Value])>,
+        display_config: (bool, Option<&[&str]>),
+        on_record: impl FnMut(HashMap<String, types::Value>) -> Result<()>,
+    ) -> Result<()> {
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_each(conn, &self.name, where_q_config, display_config, on_record)
+        })
+    }
+
+    ///
+    /// fetch the distinct values of a column of the table.
+    /// See also [`fetch::f_distinct`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `col_name` - the name of the column, it must be defined in the defaults
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    /// * `is_sorted` - whether to sort the values in ascending order
+    /// * `skip_empty` - whether to exclude NULL, blank text and empty blob values
+    pub fn distinct_values(
+        &self,
+        conn: &Connection,
+        col_name: &str,
+        where_q_config: Option<(&str, &[types::Value])>,
+        (is_sorted, skip_empty): (bool, bool),
+    ) -> Result<Vec<types::Value>> {
+        if !self.defaults.contains_key(col_name) {
+            return Err(anyhow!(
+                "(table: {}) The column '{}' does not exist",
+                self.name,
+                col_name
+            ));
+        }
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_distinct(
+                conn,
+                &self.name,
+                col_name,
+                where_q_config,
+                (is_sorted, skip_empty),
+            )
+        })
     }
 
     pub fn list_by_pk(
@@ -150,13 +681,34 @@ impl TblRep {
         pk_values: &[types::Value],
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> Result<Vec<HashMap<String, types::Value>>> {
-        fetch::f_by_pk(
-            conn,
-            &self.name,
-            (&self.pk_name, pk_values),
-            where_q_config,
-            None,
-        )
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_by_pk(
+                conn,
+                &self.name,
+                (&self.pk_name, pk_values),
+                where_q_config,
+                None,
+            )
+        })
+    }
+
+    ///
+    /// fetch records from the table by their primary keys, keyed by the text form of the primary key values.
+    /// See also [`fetch::f_by_pk_keyed`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `pk_values` - the values of the primary key
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    pub fn map_by_pk(
+        &self,
+        conn: &Connection,
+        pk_values: &[types::Value],
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<HashMap<String, HashMap<String, types::Value>>> {
+        let pk_config = (self.pk_name.as_str(), pk_values);
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_by_pk_keyed(conn, &self.name, pk_config, where_q_config, None)
+        })
     }
 
     pub fn list_by_pk_as<T: DeserializeOwned>(
@@ -166,7 +718,144 @@ impl TblRep {
         where_q_config: Option<(&str, &[types::Value])>,
     ) -> Result<Vec<T>> {
         let pk_config = (self.pk_name.as_str(), pk_values);
-        fetch::f_by_pk_as(conn, &self.name, pk_config, where_q_config, None)
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_by_pk_as(conn, &self.name, pk_config, where_q_config, None)
+        })
+    }
+
+    ///
+    /// fetch a record from the table by its primary key.
+    /// See also [`fetch::f_one_by_pk`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `pk_value` - the value of the primary key
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    /// # Returns
+    /// * `Ok(None)` - if the record is not found
+    pub fn one_by_pk(
+        &self,
+        conn: &Connection,
+        pk_value: &types::Value,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<Option<HashMap<String, types::Value>>> {
+        self.with_read_q_config(where_q_config, |where_q_config| {
+            fetch::f_one_by_pk(
+                conn,
+                &self.name,
+                (&self.pk_name, pk_value),
+                where_q_config,
+                None,
+            )
+        })
+    }
+
+    pub fn one_by_pk_as<T: DeserializeOwned>(
+        &self,
+        conn: &Connection,
+        pk_value: &types::Value,
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<Option<T>> {
+        match self.one_by_pk(conn, pk_value, where_q_config)? {
+            Some(record) => Ok(shift::list_as(&self.name, &[record])?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    ///
+    /// turn a fetched record into an input that can be passed to [`TblRep::upd_by_pk`]
+    /// * the primary key is dropped because it identifies the record rather than being updated
+    /// * columns that are not defined in the defaults are dropped
+    /// * `Value::Null` values of the fields that are not required are kept if the table allows NULL in updates,
+    ///   so the update clears them as they were read, see [`TblRep::set_allow_null`],
+    ///   otherwise they are dropped because the update would reject them
+    /// * `Value::Null` values of required fields are always dropped
+    /// # Arguments
+    /// * `record` - a record fetched from the table, e.g. an item returned by [`TblRep::list`]
+    pub fn record_to_update_input(
+        &self,
+        record: &HashMap<String, types::Value>,
+    ) -> HashMap<String, types::Value> {
+        record
+            .iter()
+            .filter(|(key, value)| {
+                key.as_str() != self.pk_name
+                    && self.defaults.contains_key(key.as_str())
+                    && (!matches!(value, types::Value::Null)
+                        || (self.update_allows_null()
+                            && !self.required_fields.contains(key.as_str())))
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    ///
+    /// collect all the problems of an input for [`TblRep::insert`] without touching the database.
+    /// See also [`verify::report_write_input_issues`]
+    /// # Arguments
+    /// * `input` - the new record to be inserted
+    /// * `default_if_absent` - whether the absent fields would be filled with their default values
+    pub fn report_insert_issues(
+        &self,
+        input: &HashMap<String, types::Value>,
+        default_if_absent: bool,
+    ) -> verify::WriteInputReport {
+        let input = if default_if_absent {
+            verify::defaults_if_absent(&self.defaults, input)
+        } else {
+            input.clone()
+        };
+        verify::report_write_input_issues(&input, &self.required_fields, &self.defaults, true)
+    }
+
+    ///
+    /// collect all the problems of an input for [`TblRep::upd_by_pk`] without touching the database.
+    /// See also [`verify::report_write_input_issues_with`]
+    /// # Arguments
+    /// * `input` - the new values for the record
+    pub fn report_update_issues(
+        &self,
+        input: &HashMap<String, types::Value>,
+    ) -> verify::WriteInputReport {
+        verify::report_write_input_issues_with(
+            &self.update_input(input),
+            &self.required_fields,
+            &self.defaults,
+            (false, self.update_allows_null()),
+        )
+    }
+
+    ///
+    /// verify an input for [`TblRep::insert`] without touching the database,
+    /// it fails with the same error as [`TblRep::insert`] would
+    /// # Arguments
+    /// * `input` - the new record to be inserted
+    /// * `default_if_absent` - whether the absent fields would be filled with their default values
+    pub fn verify_insert(
+        &self,
+        input: &HashMap<String, types::Value>,
+        default_if_absent: bool,
+    ) -> Result<()> {
+        verify::get_verified_insert_inputs(
+            self.name.as_str(),
+            input,
+            Some((&self.defaults, &self.required_fields, default_if_absent)),
+        )?;
+        Ok(())
+    }
+
+    ///
+    /// verify an input for [`TblRep::upd_by_pk`] without touching the database,
+    /// it fails with the same error as [`TblRep::upd_by_pk`] would
+    /// # Arguments
+    /// * `input` - the new values for the record
+    pub fn verify_update(&self, input: &HashMap<String, types::Value>) -> Result<()> {
+        verify::verify_required_fields_for_write_ops_with(
+            &self.update_input(input),
+            self.name.as_str(),
+            &self.required_fields,
+            &self.defaults,
+            (false, self.update_allows_null()),
+        )
     }
 
     ///
@@ -182,14 +871,184 @@ impl TblRep {
         input: &HashMap<String, types::Value>,
         default_if_absent: bool,
     ) -> Result<()> {
-        create::i_one(
+        let (input, verification_options) = self.insert_args(
+            input,
+            (&self.defaults, &self.required_fields, default_if_absent),
+        )?;
+        create::i_one(conn, self.name.as_str(), &input, verification_options)
+    }
+
+    ///
+    /// insert a new record into the table and return the inserted record.
+    /// See also [`create::i_one_returning`]
+    ///
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `input` - the new record to be inserted
+    /// * `default_if_absent` - whether to fill the absent fields with their default values
+    pub fn insert_returning(
+        &self,
+        conn: &Connection,
+        input: &HashMap<String, types::Value>,
+        default_if_absent: bool,
+    ) -> Result<HashMap<String, types::Value>> {
+        let (input, verification_options) = self.insert_args(
+            input,
+            (&self.defaults, &self.required_fields, default_if_absent),
+        )?;
+        create::i_one_returning(conn, self.name.as_str(), &input, verification_options)
+    }
+
+    ///
+    /// insert a new record into the table, generating its primary key if it's absent from the input
+    /// * a text primary key is filled with a new UUID, see [`create::new_uuid`]
+    /// * an integer primary key is left out of the insertion, so SQLite assigns the next rowid
+    ///
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `input` - the new record to be inserted, the primary key is optional
+    /// * `default_if_absent` - whether to fill the absent fields with their default values
+    /// # Returns
+    /// * `Ok(pk_value)` - the primary key value of the inserted record
+    pub fn insert_with_new_pk(
+        &self,
+        conn: &Connection,
+        input: &HashMap<String, types::Value>,
+        default_if_absent: bool,
+    ) -> Result<types::Value> {
+        let created = match (
+            input.contains_key(&self.pk_name),
+            self.defaults.get(&self.pk_name),
+        ) {
+            (true, _) => self.insert_returning(conn, input, default_if_absent)?,
+            (false, Some(types::Value::Text(_))) => {
+                let mut input = input.clone();
+                input.insert(
+                    self.pk_name.clone(),
+                    types::Value::Text(create::new_uuid(conn)?),
+                );
+                self.insert_returning(conn, &input, default_if_absent)?
+            }
+            (false, Some(types::Value::Integer(_))) => {
+                let mut defaults = self.defaults.clone();
+                defaults.remove(&self.pk_name);
+                let mut required_fields = self.required_fields.clone();
+                required_fields.remove(&self.pk_name);
+                let (input, verification_options) =
+                    self.insert_args(input, (&defaults, &required_fields, default_if_absent))?;
+                create::i_one_returning(conn, self.name.as_str(), &input, verification_options)?
+            }
+            (false, pk_default) => {
+                return Err(anyhow!(
+                    "(table: {}) The primary key '{}' can only be generated for integer or text types, but its default value is {:?}",
+                    self.name,
+                    self.pk_name,
+                    pk_default
+                ));
+            }
+        };
+        created.get(&self.pk_name).cloned().ok_or(anyhow!(
+            "(table: {}) The primary key '{}' is missing from the inserted record",
+            self.name,
+            self.pk_name
+        ))
+    }
+
+    ///
+    /// insert multiple new records into the table atomically.
+    /// See also [`create::i_many`]
+    ///
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `inputs` - the new records to be inserted
+    /// * `default_if_absent` - whether to fill the absent fields with their default values
+    pub fn insert_many(
+        &self,
+        conn: &Connection,
+        inputs: &[HashMap<String, types::Value>],
+        default_if_absent: bool,
+    ) -> Result<()> {
+        if !self.empty_as_null {
+            return create::i_many(
+                conn,
+                self.name.as_str(),
+                inputs,
+                Some((&self.defaults, &self.required_fields, default_if_absent)),
+            );
+        }
+        let table_prefix = format!("(table: {}) ", self.name);
+        let mut ready_inputs = Vec::with_capacity(inputs.len());
+        for (index, input) in inputs.iter().enumerate() {
+            let verification_options = (&self.defaults, &self.required_fields, default_if_absent);
+            let (input, _) = self
+                .insert_args(input, verification_options)
+                .map_err(|err| {
+                    let err = err.to_string();
+                    anyhow!(
+                        "{}The record at index {} cannot be inserted: {}",
+                        table_prefix,
+                        index,
+                        err.strip_prefix(&table_prefix).unwrap_or(&err)
+                    )
+                })?;
+            ready_inputs.push(input.into_owned());
+        }
+        create::i_many(conn, self.name.as_str(), &ready_inputs, None)
+    }
+
+    ///
+    /// insert a new record into the table, or update the record with the same primary key.
+    /// See also [`create::i_upsert`]
+    ///
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `input` - the record to be inserted or updated, it must contain the primary key
+    /// * `default_if_absent` - whether to fill the absent fields with their default values when inserting,
+    ///   the absent fields are never changed when updating
+    pub fn upsert(
+        &self,
+        conn: &Connection,
+        input: &HashMap<String, types::Value>,
+        default_if_absent: bool,
+    ) -> Result<()> {
+        create::i_upsert(
             conn,
             self.name.as_str(),
+            &self.pk_name,
             input,
             Some((&self.defaults, &self.required_fields, default_if_absent)),
         )
     }
 
+    ///
+    /// insert a new record into the table, or do nothing if the record with the same primary key exists.
+    /// See also [`create::i_or_ignore`]
+    ///
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `input` - the record to be inserted, it must contain the primary key
+    /// * `default_if_absent` - whether to fill the absent fields with their default values
+    /// # Returns
+    /// * `Ok(inserted_rows)` - 1 if the record is inserted, 0 if it's ignored
+    pub fn insert_or_ignore(
+        &self,
+        conn: &Connection,
+        input: &HashMap<String, types::Value>,
+        default_if_absent: bool,
+    ) -> Result<usize> {
+        let (input, verification_options) = self.insert_args(
+            input,
+            (&self.defaults, &self.required_fields, default_if_absent),
+        )?;
+        create::i_or_ignore(
+            conn,
+            self.name.as_str(),
+            &self.pk_name,
+            &input,
+            verification_options,
+        )
+    }
+
     ///
     /// update an existing record in the table
     /// See also [`crud::update`]
@@ -199,23 +1058,78 @@ impl TblRep {
     /// * `input` - the new values for the record
     /// * `where_q_config` - the where clause and the parameters for the where clause
     /// # Returns
-    /// * `Ok(())` - if the record is updated successfully
+    /// * `Ok(affected_rows)` - the number of records that are updated
+    /// * `Err` - if no record matches and [`TblRep::set_require_match`] is on
     pub fn upd_by_pk(
         &self,
         conn: &Connection,
         pk_values: &[types::Value],
         input: &HashMap<String, types::Value>,
         where_q_config: Option<(&str, &[types::Value])>,
-    ) -> Result<()> {
-        update::u_by_pk(
+    ) -> Result<usize> {
+        let affected_rows = update::u_by_pk(
             conn,
             self.name.as_str(),
             &self.pk_name,
             pk_values,
-            input,
+            &self.update_input(input),
             where_q_config,
-            Some((&self.defaults, &self.required_fields)),
-        )
+            Some(self.update_verification()),
+        )?;
+        self.verify_update_matched(affected_rows)
+    }
+
+    ///
+    /// update multiple records of the table, each with its own values, e.g. reordering a playlist.
+    /// All the updates are applied atomically, if any of the inputs is invalid, nothing is changed
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `updates` - pairs of the primary key value of a record and the new values for it
+    /// # Returns
+    /// * `Ok(affected_rows)` - the total number of records that are updated
+    /// * `Err` - if any of the records is not found and [`TblRep::set_require_match`] is on,
+    ///   nothing is changed in that case
+    pub fn upd_each(
+        &self,
+        conn: &Connection,
+        updates: &[(types::Value, HashMap<String, types::Value>)],
+    ) -> Result<usize> {
+        atomic::in_savepoint(conn, "upd_each", || {
+            let mut affected_rows = 0;
+            for (pk_value, input) in updates {
+                affected_rows +=
+                    self.upd_by_pk(conn, std::slice::from_ref(pk_value), input, None)?;
+            }
+            Ok(affected_rows)
+        })
+    }
+
+    ///
+    /// update all records of the table that match a filter,
+    /// the filter cannot be empty, so all records are never updated by accident.
+    /// See also [`update::u_all`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `filter` - the condition of the records to update, see [`TblRep::filter_q_config`]
+    /// * `input` - the new values for the records
+    /// # Returns
+    /// * `Ok(affected_rows)` - the number of records that are updated
+    /// * `Err` - if no record matches and [`TblRep::set_require_match`] is on
+    pub fn upd_by_filter(
+        &self,
+        conn: &Connection,
+        filter: &sql::Filter,
+        input: &HashMap<String, types::Value>,
+    ) -> Result<usize> {
+        let (where_clause, where_params) = self.filter_q_config(filter)?;
+        let affected_rows = update::u_all(
+            conn,
+            self.name.as_str(),
+            &self.update_input(input),
+            (where_clause.as_str(), &where_params),
+            Some(self.update_verification()),
+        )?;
+        self.verify_update_matched(affected_rows)
     }
 
     ///
@@ -226,13 +1140,80 @@ impl TblRep {
     /// * `pk_values` - records to be deleted represented by their primary key values
     /// * `where_q_config` - the where clause and the parameters for the where clause
     /// # Returns
-    /// * `Ok(())` - if the record is deleted successfully
+    /// * `Ok(affected_rows)` - the number of records that are deleted
     pub fn del_by_pk(
         &self,
         conn: &Connection,
         pk_values: &[types::Value],
         where_q_config: Option<(&str, &[types::Value])>,
-    ) -> Result<()> {
+    ) -> Result<usize> {
         del::d_by_pk(conn, &self.name, &self.pk_name, pk_values, where_q_config)
     }
+
+    ///
+    /// delete all records of the table that match a filter,
+    /// the filter cannot be empty, so all records are never deleted by accident.
+    /// See also [`del::d_all`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `filter` - the condition of the records to delete, see [`TblRep::filter_q_config`]
+    /// * `max_rows` - if provided, nothing is deleted when more records than this match
+    /// # Returns
+    /// * `Ok(affected_rows)` - the number of records that are deleted
+    pub fn del_by_filter(
+        &self,
+        conn: &Connection,
+        filter: &sql::Filter,
+        max_rows: Option<usize>,
+    ) -> Result<usize> {
+        let (where_clause, where_params) = self.filter_q_config(filter)?;
+        let where_q_config = (where_clause.as_str(), where_params.as_slice());
+        atomic::in_savepoint(conn, "del_by_filter", || {
+            if let Some(max_rows) = max_rows {
+                let matched = total::t_all(conn, &self.name, None, Some(where_q_config))?;
+                if matched as usize > max_rows {
+                    return Err(anyhow!(
+                        "(table: {}) {} records match the condition, which exceeds the limit of {}",
+                        self.name,
+                        matched,
+                        max_rows
+                    ));
+                }
+            }
+            del::d_all(conn, &self.name, where_q_config)
+        })
+    }
+
+    ///
+    /// soft-delete records from the table by setting their `deleted_at` column,
+    /// the table must define `deleted_at` in its defaults with an empty text default value.
+    /// See also [`del::d_soft_by_pk`]
+    /// # Arguments
+    /// * `conn` - the Rusqlite connection to the database
+    /// * `pk_values` - records to be deleted represented by their primary key values
+    /// * `where_q_config` - the where clause and the parameters for the where clause
+    /// # Returns
+    /// * `Ok(affected_rows)` - the number of records that are newly soft-deleted
+    pub fn soft_del_by_pk(
+        &self,
+        conn: &Connection,
+        pk_values: &[types::Value],
+        where_q_config: Option<(&str, &[types::Value])>,
+    ) -> Result<usize> {
+        if !self.defaults.contains_key(del::SOFT_DEL_COL) {
+            return Err(anyhow!(
+                "(table: {}) Soft deletion requires the column '{}'",
+                self.name,
+                del::SOFT_DEL_COL
+            ));
+        }
+        if !self.is_soft_deletable() {
+            return Err(anyhow!(
+                "(table: {}) Soft deletion requires the default value of '{}' to be an empty text",
+                self.name,
+                del::SOFT_DEL_COL
+            ));
+        }
+        del::d_soft_by_pk(conn, &self.name, &self.pk_name, pk_values, where_q_config)
+    }
 }
@@ -1,4 +1,6 @@
 pub mod bond;
 pub mod crud;
 pub mod db_rep;
+pub mod migrate;
+pub mod setup;
 pub use db_rep::TblRep;
@@ -0,0 +1,83 @@
+use crate::crud::atomic::in_savepoint;
+
+use anyhow::{anyhow, Result};
+use rusqlite::Connection;
+
+///
+/// the table that records the versions of the applied migrations
+pub const MIGRATION_TABLE: &str = "_janken_migrations";
+
+///
+/// A schema change that is applied once by [`run_migrations`]
+/// * `version` - the version of the migration, the versions must be consecutive from 1
+/// * `up_sql` - the SQL statements of the migration, they can be separated by `;`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration<'a> {
+    pub version: i64,
+    pub up_sql: &'a str,
+}
+
+///
+/// apply the migrations that haven't been applied yet in the order of their versions,
+/// each migration is applied atomically together with the record of its version,
+/// so running the same migrations again is a no-op
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `migrations` - all the migrations in ascending order of their versions,
+///   including the ones that have been applied
+/// # Returns
+/// * `Ok(applied_count)` - the number of migrations that are applied by this call
+pub fn run_migrations(conn: &Connection, migrations: &[Migration]) -> Result<usize> {
+    for (index, migration) in migrations.iter().enumerate() {
+        let expected_version = index as i64 + 1;
+        if migration.version != expected_version {
+            return Err(anyhow!(
+                "The migration versions must be consecutive from 1, expected version {} but received {}",
+                expected_version,
+                migration.version
+            ));
+        }
+    }
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (version INTEGER PRIMARY KEY, applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP)",
+        MIGRATION_TABLE
+    ))?;
+    let (applied_count, latest_version): (i64, Option<i64>) = conn.query_row(
+        &format!("SELECT COUNT(*), MAX(version) FROM {}", MIGRATION_TABLE),
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let latest_version = latest_version.unwrap_or(0);
+    if applied_count != latest_version {
+        return Err(anyhow!(
+            "The applied migrations have gaps, {} versions are recorded but the latest one is {}",
+            applied_count,
+            latest_version
+        ));
+    }
+    if latest_version > migrations.len() as i64 {
+        return Err(anyhow!(
+            "The database is at version {}, which is newer than the latest migration {}",
+            latest_version,
+            migrations.len()
+        ));
+    }
+    let pending = &migrations[latest_version as usize..];
+    for migration in pending {
+        in_savepoint(conn, "run_migration", || {
+            conn.execute_batch(migration.up_sql).map_err(|err| {
+                anyhow!(
+                    "The migration of version {} cannot be applied: {}",
+                    migration.version,
+                    err
+                )
+            })?;
+            conn.execute(
+                &format!("INSERT INTO {} (version) VALUES (?)", MIGRATION_TABLE),
+                [migration.version],
+            )?;
+            Ok(())
+        })?;
+    }
+    Ok(pending.len())
+}
@@ -0,0 +1,51 @@
+use anyhow::{anyhow, Result};
+use rusqlite::Connection;
+
+use std::time::Duration;
+
+///
+/// the busy timeout used by [`apply_recommended_pragmas`]
+pub const RECOMMENDED_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+///
+/// switch the database to write-ahead logging,
+/// so that readers don't block the writer and the writer doesn't block readers.
+/// In-memory databases don't support WAL and keep their `memory` journal mode
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+pub fn enable_wal(conn: &Connection) -> Result<()> {
+    let mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+    match mode.to_lowercase().as_str() {
+        "wal" | "memory" => Ok(()),
+        _ => Err(anyhow!(
+            "The journal mode cannot be switched to WAL, it remains '{}'",
+            mode
+        )),
+    }
+}
+
+///
+/// set how long a statement waits for a lock held by another connection
+/// before it fails with `database is locked`
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+/// * `timeout` - the maximum time to wait for a lock
+pub fn set_busy_timeout(conn: &Connection, timeout: Duration) -> Result<()> {
+    conn.busy_timeout(timeout)?;
+    Ok(())
+}
+
+///
+/// apply the pragmas that suit most local applications:
+/// * WAL journal mode, see [`enable_wal`]
+/// * a busy timeout of [`RECOMMENDED_BUSY_TIMEOUT`], see [`set_busy_timeout`]
+/// * `synchronous = NORMAL`, which is safe with WAL and much faster than `FULL`
+/// * `foreign_keys = ON`, which SQLite leaves off by default
+/// # Arguments
+/// * `conn` - the Rusqlite connection to the database
+pub fn apply_recommended_pragmas(conn: &Connection) -> Result<()> {
+    enable_wal(conn)?;
+    set_busy_timeout(conn, RECOMMENDED_BUSY_TIMEOUT)?;
+    conn.execute_batch("PRAGMA synchronous = NORMAL; PRAGMA foreign_keys = ON;")?;
+    Ok(())
+}
@@ -0,0 +1,118 @@
+mod helpers;
+use helpers::initialize_db;
+
+use jankenstore::{
+    bond::wrap::NnWrap,
+    crud::{
+        atomic::{in_transaction, with_rollback},
+        shift::val::{v_int, v_txt},
+    },
+    TblRep,
+};
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+fn get_song_album_reps() -> anyhow::Result<(TblRep, TblRep)> {
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name", "artist_id"],
+    )?;
+    let album_rep = TblRep::new(
+        "album",
+        "id",
+        &[("id", v_int(0)), ("name", v_txt(""))],
+        &["name"],
+    )?;
+    Ok((song_rep, album_rep))
+}
+
+#[test]
+fn test_in_transaction() -> anyhow::Result<()> {
+    let mut conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (song_rep, album_rep) = get_song_album_reps()?;
+    let songs_albums_nn = NnWrap::new(
+        &song_rep,
+        &album_rep,
+        ("rel_album_song", "song_id", "album_id"),
+    );
+    let new_song = HashMap::from([
+        ("id".to_string(), v_int(7)),
+        ("name".to_string(), v_txt("Bohemian Rhapsody")),
+        ("artist_id".to_string(), v_int(6)),
+    ]);
+
+    let err = in_transaction(&mut conn, |tx| {
+        song_rep.insert(tx, &new_song, true)?;
+        songs_albums_nn.link(tx, &[v_int(7)], &[v_int(1), v_int(2)])?;
+        songs_albums_nn.link(tx, &[v_int(7)], &[v_txt("")])
+    })
+    .err()
+    .unwrap();
+    assert!(err.to_string().starts_with("(table: rel_album_song)"));
+    assert_eq!(song_rep.count(&conn, None, None)?, 6);
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t2(&conn, &[v_int(7)], None, None)?,
+        0
+    );
+
+    let linked = in_transaction(&mut conn, |tx| {
+        song_rep.insert(tx, &new_song, true)?;
+        songs_albums_nn.link(tx, &[v_int(7)], &[v_int(1), v_int(2)])?;
+        songs_albums_nn.count_peers_of_t2(tx, &[v_int(7)], None, None)
+    })?;
+    assert_eq!(linked, 2);
+    assert_eq!(song_rep.count(&conn, None, None)?, 7);
+
+    Ok(())
+}
+
+#[test]
+fn test_with_rollback() -> anyhow::Result<()> {
+    let mut conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (song_rep, album_rep) = get_song_album_reps()?;
+    let songs_albums_nn = NnWrap::new(
+        &song_rep,
+        &album_rep,
+        ("rel_album_song", "song_id", "album_id"),
+    );
+    let new_song = HashMap::from([
+        ("id".to_string(), v_int(7)),
+        ("name".to_string(), v_txt("Bohemian Rhapsody")),
+        ("artist_id".to_string(), v_int(6)),
+    ]);
+
+    let previewed = with_rollback(&mut conn, |tx| {
+        song_rep.insert(tx, &new_song, true)?;
+        songs_albums_nn.link(tx, &[v_int(7)], &[v_int(1), v_int(2)])?;
+        Ok((
+            song_rep.count(tx, None, None)?,
+            songs_albums_nn.count_peers_of_t2(tx, &[v_int(7)], None, None)?,
+        ))
+    })?;
+    assert_eq!(previewed, (7, 2));
+    assert_eq!(song_rep.count(&conn, None, None)?, 6);
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t2(&conn, &[v_int(7)], None, None)?,
+        0
+    );
+
+    let err = with_rollback(&mut conn, |tx| {
+        song_rep.insert(tx, &new_song, true)?;
+        songs_albums_nn.link(tx, &[v_int(7)], &[v_txt("")])
+    })
+    .err()
+    .unwrap();
+    assert!(err.to_string().starts_with("(table: rel_album_song)"));
+    assert_eq!(song_rep.count(&conn, None, None)?, 6);
+
+    Ok(())
+}
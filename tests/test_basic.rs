@@ -1,7 +1,12 @@
-use jankenstore::{crud::shift::val_to_json, TblRep};
+use jankenstore::{
+    crud::shift::{list_as, val_to_json, val_to_json_with, BlobEncoding},
+    TblRep,
+};
 
 use anyhow::Result;
+use insta::assert_snapshot;
 use rusqlite::{types, Connection};
+use std::collections::HashMap;
 
 #[test]
 fn test_observability() -> Result<()> {
@@ -42,7 +47,7 @@ fn test_observability() -> Result<()> {
 
 #[test]
 fn test_json_conversion() -> Result<()> {
-    let mut map = std::collections::HashMap::new();
+    let mut map = HashMap::new();
     map.insert("id".to_string(), types::Value::Integer(1));
     map.insert("name".to_string(), types::Value::Text("test".to_string()));
     map.insert("count".to_string(), types::Value::Integer(2));
@@ -76,3 +81,177 @@ fn test_json_conversion() -> Result<()> {
     assert_eq!(json["joke"], serde_json::Value::Null);
     Ok(())
 }
+
+#[test]
+fn test_json_conversion_with_base64_blobs() -> Result<()> {
+    let cases = [
+        (vec![], ""),
+        (b"f".to_vec(), "Zg=="),
+        (b"fo".to_vec(), "Zm8="),
+        (b"foo".to_vec(), "Zm9v"),
+        (b"foobar".to_vec(), "Zm9vYmFy"),
+        (vec![0, 255, 128, 64], "AP+AQA=="),
+    ];
+    for (blob, expected) in cases {
+        let map = HashMap::from([
+            ("id".to_string(), types::Value::Integer(1)),
+            ("file".to_string(), types::Value::Blob(blob)),
+        ]);
+        let json = val_to_json_with(&map, BlobEncoding::Base64)?;
+        assert_eq!(
+            json["file"],
+            serde_json::Value::String(expected.to_string())
+        );
+        assert_eq!(json["id"], serde_json::Value::Number(1.into()));
+        assert_eq!(
+            val_to_json_with(&map, BlobEncoding::ByteArray)?,
+            val_to_json(&map)?
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_read_stored_blobs_with_encoding() -> Result<()> {
+    #[derive(Debug, serde::Deserialize)]
+    struct Attachment {
+        id: i64,
+        file: String,
+    }
+    #[derive(Debug, serde::Deserialize)]
+    struct RawAttachment {
+        file: Vec<u8>,
+    }
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE attachment (id INTEGER PRIMARY KEY, file BLOB)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "attachment",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("file", types::Value::Blob(vec![])),
+        ],
+        &[],
+    )?;
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("file".to_string(), types::Value::Blob(b"foobar".to_vec())),
+    ]);
+    tbl_rep.insert(&conn, &input, true)?;
+
+    let records = tbl_rep.list_json(&conn, None, (false, None), BlobEncoding::Base64)?;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["file"], serde_json::json!("Zm9vYmFy"));
+    let records = tbl_rep.list_json(&conn, None, (false, None), BlobEncoding::ByteArray)?;
+    assert_eq!(records[0]["file"], serde_json::json!(b"foobar"));
+
+    let attachments =
+        tbl_rep.list_as_with::<Attachment>(&conn, None, (false, None), BlobEncoding::Base64)?;
+    assert_eq!(attachments[0].id, 1);
+    assert_eq!(attachments[0].file, "Zm9vYmFy");
+    let raw_attachments = tbl_rep.list_as_with::<RawAttachment>(
+        &conn,
+        None,
+        (false, None),
+        BlobEncoding::ByteArray,
+    )?;
+    assert_eq!(raw_attachments[0].file, b"foobar");
+
+    let err = tbl_rep
+        .list_as_with::<Attachment>(&conn, None, (false, None), BlobEncoding::ByteArray)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: attachment) The record at index 0 cannot be converted at the field 'file': invalid type: sequence, expected a string"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_queryable_columns() -> Result<()> {
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("score", types::Value::Real(0.0)),
+            ("memo", types::Value::Text("".to_string())),
+            ("file", types::Value::Blob(vec![])),
+        ],
+        &["name"],
+    )?;
+    let queryable = tbl_rep.queryable_columns();
+    assert_eq!(queryable.sortable, vec!["id", "memo", "name", "score"]);
+    assert_eq!(queryable.filterable, vec!["id", "memo", "name", "score"]);
+    assert_eq!(queryable.searchable, vec!["memo", "name"]);
+    Ok(())
+}
+
+#[test]
+fn test_typed_conversion_errors() -> Result<()> {
+    #[derive(Debug, serde::Deserialize)]
+    struct Named {
+        name: String,
+        memo: Option<String>,
+    }
+    let rows = vec![
+        HashMap::from([
+            ("name".to_string(), types::Value::Text("test".to_string())),
+            ("memo".to_string(), types::Value::Null),
+        ]),
+        HashMap::from([
+            ("name".to_string(), types::Value::Integer(2)),
+            ("memo".to_string(), types::Value::Text("secret".to_string())),
+        ]),
+    ];
+    let err = list_as::<Named>("test", &rows).err().unwrap();
+    assert_snapshot!(err.to_string());
+
+    let missing_rows = vec![HashMap::from([(
+        "memo".to_string(),
+        types::Value::Text("secret".to_string()),
+    )])];
+    let err = list_as::<Named>("test", &missing_rows).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The record at index 0 cannot be converted: missing field `name`"
+    );
+
+    let memo_rows = vec![HashMap::from([
+        ("name".to_string(), types::Value::Text("test".to_string())),
+        ("memo".to_string(), types::Value::Blob(b"secret".to_vec())),
+    ])];
+    let err = list_as::<Named>("test", &memo_rows).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The record at index 0 cannot be converted at the field 'memo': invalid type: sequence, expected a string"
+    );
+
+    let converted = list_as::<Named>("test", &rows[..1])?;
+    assert_eq!(converted[0].name, "test");
+    assert_eq!(converted[0].memo, None);
+    Ok(())
+}
+
+#[test]
+fn test_typescript_export() -> Result<()> {
+    let tbl_rep = TblRep::new(
+        "song_file",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("score", types::Value::Real(0.0)),
+            ("memo", types::Value::Text("".to_string())),
+            ("file", types::Value::Blob(vec![])),
+        ],
+        &["name", "file"],
+    )?;
+    assert_snapshot!(tbl_rep.to_typescript());
+    Ok(())
+}
@@ -60,12 +60,6 @@ fn get_wraps<'a>(
     Ok((n1_wrap, n2_wrap))
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Artist {
-    id: i64,
-    name: String,
-}
-
 #[derive(Debug, Deserialize, Serialize)]
 struct Song {
     id: i64,
@@ -339,7 +333,7 @@ fn test_insert_with_nn() -> anyhow::Result<()> {
         let albums_of_song = bond::fetch::list_n_of_n(
             &conn,
             ("album", "id", "album_id"),
-            ("rel_album_song", "song_id", &[song_id.clone()]),
+            ("rel_album_song", "song_id", std::slice::from_ref(song_id)),
             None,
             None,
         )
@@ -357,7 +351,7 @@ fn test_insert_with_nn() -> anyhow::Result<()> {
         let songs_of_album = bond::fetch::list_n_of_n(
             &conn,
             ("song", "id", "song_id"),
-            ("rel_album_song", "album_id", &[album_id.clone()]),
+            ("rel_album_song", "album_id", std::slice::from_ref(album_id)),
             None,
             None,
         )
@@ -456,3 +450,509 @@ fn test_insert_with_nn_errors() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_read_top_kids() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (artist_rep, song_rep, album_rep) = get_infos()?;
+    let (songs_artists_n1, _) = get_wraps((&artist_rep, &song_rep, &album_rep))?;
+
+    let latest_songs = songs_artists_n1.list_top_kids(
+        &conn,
+        &[v_int(5), v_int(3)],
+        ("id", true, 1),
+        Some(&["id", "name"]),
+        None,
+    )?;
+    assert_eq!(latest_songs.len(), 2);
+    assert_eq!(latest_songs[0].get("id"), Some(&v_int(3)));
+    assert_eq!(latest_songs[1].get("id"), Some(&v_int(6)));
+    assert_eq!(latest_songs[1].len(), 2);
+
+    let songs = songs_artists_n1.list_top_kids(
+        &conn,
+        &[v_int(5)],
+        ("name", false, 5),
+        None,
+        Some(("memo != ?", &[v_txt("2000s")])),
+    )?;
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0].get("name"), Some(&v_txt("We Are!")));
+    assert_eq!(songs[0].len(), 4);
+
+    let unknown_order_err = songs_artists_n1
+        .list_top_kids(&conn, &[v_int(5)], ("rank", false, 1), None, None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        unknown_order_err.to_string(),
+        "(table: song) The column 'rank' cannot be used for ordering"
+    );
+
+    let no_limit_err = bond::fetch::list_top_n_of_1(
+        &conn,
+        "song",
+        ("artist_id", &[v_int(5)]),
+        ("id", false, 0),
+        None,
+        None,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        no_limit_err.to_string(),
+        "(table: song) At least 1 record per parent is required for this operation"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_count_bonds() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (artist_rep, song_rep, album_rep) = get_infos()?;
+    let (songs_artists_n1, songs_albums_nn) = get_wraps((&artist_rep, &song_rep, &album_rep))?;
+
+    assert_eq!(
+        songs_artists_n1.count_kids(&conn, &[v_int(5)], None, None)?,
+        2
+    );
+    assert_eq!(
+        songs_artists_n1.count_kids(&conn, &[v_int(1), v_int(5)], Some("artist_id"), None)?,
+        2
+    );
+    assert_eq!(
+        songs_artists_n1.count_kids(
+            &conn,
+            &[v_int(5)],
+            None,
+            Some(("memo = ?", &[v_txt("90s")]))
+        )?,
+        1
+    );
+
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t1(&conn, &[v_int(1)], None, None)?,
+        4
+    );
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t1(&conn, &[v_int(1)], Some("memo"), None)?,
+        3
+    );
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t2(&conn, &[v_int(5)], None, None)?,
+        2
+    );
+    assert_eq!(
+        bond::fetch::count_n_of_n(
+            &conn,
+            ("album", "id", "album_id"),
+            ("rel_album_song", "song_id", &[v_int(4)]),
+            None,
+            None,
+        )?,
+        0
+    );
+    Ok(())
+}
+
+#[test]
+fn test_read_kids_with_parent() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (artist_rep, song_rep, album_rep) = get_infos()?;
+    let (songs_artists_n1, _) = get_wraps((&artist_rep, &song_rep, &album_rep))?;
+
+    let songs = songs_artists_n1.list_kids_with_parent(&conn, &[v_int(3), v_int(5)], None, None)?;
+    assert_eq!(songs.len(), 3);
+    let we_go = songs
+        .iter()
+        .find(|song| song.get("id") == Some(&v_int(6)))
+        .unwrap();
+    assert_eq!(we_go.get("name"), Some(&v_txt("We Go!")));
+    assert_eq!(we_go.get("artist__id"), Some(&v_int(5)));
+    assert_eq!(we_go.get("artist__name"), Some(&v_txt("Hiroshi Kitadani")));
+    assert_eq!(we_go.len(), 6);
+
+    let songs = songs_artists_n1.list_kids_with_parent(
+        &conn,
+        &[v_int(3), v_int(5)],
+        Some(&["id", "name"]),
+        Some(("name = ?", &[v_txt("A Hard Day's Night")])),
+    )?;
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0].get("artist__name"), Some(&v_txt("The Beatles")));
+    assert_eq!(songs[0].len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_delete_all_nn_bonds_of_records() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+
+    let (artist_rep, song_rep, album_rep) = get_infos()?;
+    let (_, songs_albums_nn) = get_wraps((&artist_rep, &song_rep, &album_rep))?;
+
+    songs_albums_nn.unlink_all_t2(&conn, &[v_int(1)])?;
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t1(&conn, &[v_int(1)], None, None)?,
+        0
+    );
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t1(&conn, &[v_int(2)], None, None)?,
+        1
+    );
+    assert_eq!(album_rep.count(&conn, None, None)?, 2);
+    assert_eq!(song_rep.count(&conn, None, None)?, 6);
+
+    songs_albums_nn.unlink_all_t1(&conn, &[v_int(5)])?;
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t1(&conn, &[v_int(2)], None, None)?,
+        0
+    );
+
+    let no_key_err = songs_albums_nn.unlink_all_t1(&conn, &[]).err().unwrap();
+    assert_snapshot!(no_key_err.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_delete_all_nn_bonds_of_many_records() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute("CREATE TABLE rel (a_id INTEGER, b_id INTEGER)", [])?;
+    let a_ids = (1..=1600).map(v_int).collect::<Vec<_>>();
+    relink::nn(
+        &conn,
+        "rel",
+        ("a_id", &a_ids),
+        ("b_id", &[v_int(1), v_int(2)]),
+    )?;
+    assert_eq!(crud::total::t_all(&conn, "rel", None, None)?, 3200);
+
+    relink::d_all_of(&conn, "rel", ("a_id", &a_ids[..1500]))?;
+    assert_eq!(crud::total::t_all(&conn, "rel", None, None)?, 200);
+    assert_eq!(
+        crud::total::t_all(&conn, "rel", None, Some(("a_id <= ?", &[v_int(1500)])))?,
+        0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_list_orphan_kids() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (artist_rep, song_rep, album_rep) = get_infos()?;
+    let (songs_artists_n1, _) = get_wraps((&artist_rep, &song_rep, &album_rep))?;
+
+    assert!(songs_artists_n1.list_orphan_kids(&conn)?.is_empty());
+
+    conn.execute("PRAGMA foreign_keys = OFF", [])?;
+    conn.execute("DELETE FROM artist WHERE id = 5", [])?;
+    conn.execute("UPDATE song SET artist_id = NULL WHERE id = 1", [])?;
+    assert_eq!(songs_artists_n1.list_orphan_kids(&conn)?, [5, 6].map(v_int));
+
+    Ok(())
+}
+
+#[test]
+fn test_relink_all_nn_bonds() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (artist_rep, song_rep, album_rep) = get_infos()?;
+    let (_, songs_albums_nn) = get_wraps((&artist_rep, &song_rep, &album_rep))?;
+
+    let missing_album_err = songs_albums_nn
+        .relink_t2(&conn, &v_int(1), &v_int(3))
+        .err()
+        .unwrap();
+    assert_snapshot!(missing_album_err.to_string());
+
+    let moved = songs_albums_nn.relink_t2(&conn, &v_int(1), &v_int(2))?;
+    assert_eq!(moved, 3);
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t1(&conn, &[v_int(1)], None, None)?,
+        0
+    );
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t1(&conn, &[v_int(2)], None, None)?,
+        4
+    );
+    assert_eq!(crud::total::t_all(&conn, "rel_album_song", None, None)?, 4);
+
+    let moved = songs_albums_nn.relink_t1(&conn, &v_int(5), &v_int(4))?;
+    assert_eq!(moved, 1);
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t2(&conn, &[v_int(4)], None, None)?,
+        1
+    );
+    assert_eq!(
+        songs_albums_nn.count_peers_of_t2(&conn, &[v_int(5)], None, None)?,
+        0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_link_nn_in_batches() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (artist_rep, song_rep, album_rep) = get_infos()?;
+    let (_, songs_albums_nn) = get_wraps((&artist_rep, &song_rep, &album_rep))?;
+
+    songs_albums_nn.link(
+        &conn,
+        &[v_int(1), v_int(4), v_int(1), v_int(4)],
+        &[v_int(1), v_int(2)],
+    )?;
+    assert_eq!(crud::total::t_all(&conn, "rel_album_song", None, None)?, 8);
+    songs_albums_nn.link(&conn, &[v_txt("1")], &[v_txt("1"), v_int(2)])?;
+    assert_eq!(crud::total::t_all(&conn, "rel_album_song", None, None)?, 8);
+    assert_eq!(
+        crud::total::t_all(
+            &conn,
+            "rel_album_song",
+            Some("song_id || '-' || album_id"),
+            None
+        )?,
+        8
+    );
+
+    for id in 7..=700 {
+        conn.execute(
+            "INSERT INTO song (id, name, artist_id, memo) VALUES (?, ?, 1, '')",
+            (id, format!("song {}", id)),
+        )?;
+    }
+    let song_ids = (1..=700).map(v_int).collect::<Vec<_>>();
+    songs_albums_nn.link(&conn, &song_ids, &[v_int(1), v_int(2)])?;
+    assert_eq!(
+        crud::total::t_all(&conn, "rel_album_song", None, None)?,
+        1400
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_read_kids_of_many_parents() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute_batch(
+        "CREATE TABLE parent (id INTEGER PRIMARY KEY);
+        CREATE TABLE kid (id INTEGER PRIMARY KEY, parent_id INTEGER, name TEXT);",
+    )?;
+    for id in 1..=1500 {
+        conn.execute("INSERT INTO parent (id) VALUES (?)", [id])?;
+        conn.execute(
+            "INSERT INTO kid (id, parent_id, name) VALUES (?, ?, ?)",
+            (id, id, format!("kid {}", id)),
+        )?;
+    }
+    let parent_ids = (1..=1500).map(v_int).collect::<Vec<_>>();
+
+    let kids = bond::fetch::list_n_of_1(&conn, "kid", ("parent_id", &parent_ids), None, None)?;
+    assert_eq!(kids.len(), 1500);
+
+    let where_params = (1..=600)
+        .map(|i| v_txt(&format!("kid {}", i)))
+        .collect::<Vec<_>>();
+    let where_clause = format!("name NOT IN ({})", vec!["?"; where_params.len()].join(", "));
+    let kids = bond::fetch::list_n_of_1(
+        &conn,
+        "kid",
+        ("parent_id", &parent_ids),
+        Some(&["id"]),
+        Some((where_clause.as_str(), &where_params)),
+    )?;
+    assert_eq!(kids.len(), 900);
+
+    let kids = bond::fetch::list_n_of_1_with_parent(
+        &conn,
+        "kid",
+        ("parent", "id", &["id"]),
+        ("parent_id", &parent_ids),
+        Some(&["id"]),
+        Some((where_clause.as_str(), &where_params)),
+    )?;
+    assert_eq!(kids.len(), 900);
+    assert!(kids
+        .iter()
+        .all(|kid| kid.get("id") == kid.get("parent__id")));
+
+    let reversed_parent_ids = parent_ids.iter().rev().cloned().collect::<Vec<_>>();
+    let kids = bond::fetch::list_top_n_of_1(
+        &conn,
+        "kid",
+        ("parent_id", &reversed_parent_ids),
+        ("id", false, 1),
+        Some(&["id"]),
+        Some((where_clause.as_str(), &where_params)),
+    )?;
+    assert_eq!(
+        kids.iter().map(|kid| kid["id"].clone()).collect::<Vec<_>>(),
+        (601..=1500).map(v_int).collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+// a rough benchmark of linking n-n records, it counts the executed statements
+// and compares them with checking and inserting the links pair by pair, run it by
+// `cargo test --release --test test_bonds -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_link_nn() -> anyhow::Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    static STATEMENTS: AtomicUsize = AtomicUsize::new(0);
+    fn count_statement(_: &str) {
+        STATEMENTS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let mut conn = Connection::open_in_memory()?;
+    conn.execute_batch(
+        "CREATE TABLE rel_batch (a_id INTEGER, b_id INTEGER);
+        CREATE TABLE rel_pairs (a_id INTEGER, b_id INTEGER);",
+    )?;
+    let a_ids = (1..=100).map(v_int).collect::<Vec<_>>();
+    let b_ids = (1..=50).map(v_int).collect::<Vec<_>>();
+
+    conn.trace(Some(count_statement));
+    let started = Instant::now();
+    relink::nn(&conn, "rel_batch", ("a_id", &a_ids), ("b_id", &b_ids))?;
+    let batch_elapsed = started.elapsed();
+    let batch_statements = STATEMENTS.swap(0, Ordering::Relaxed);
+
+    let started = Instant::now();
+    for a_id in &a_ids {
+        for b_id in &b_ids {
+            let existed: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM rel_pairs WHERE a_id = ? AND b_id = ?)",
+                [a_id, b_id],
+                |row| row.get(0),
+            )?;
+            if !existed {
+                conn.execute(
+                    "INSERT INTO rel_pairs (a_id, b_id) VALUES (?, ?)",
+                    [a_id, b_id],
+                )?;
+            }
+        }
+    }
+    let pairs_elapsed = started.elapsed();
+    let pairs_statements = STATEMENTS.swap(0, Ordering::Relaxed);
+    conn.trace(None);
+
+    println!(
+        "relink::nn: {} statements, {:?}",
+        batch_statements, batch_elapsed
+    );
+    println!(
+        "pair by pair: {} statements, {:?}",
+        pairs_statements, pairs_elapsed
+    );
+    assert_eq!(crud::total::t_all(&conn, "rel_batch", None, None)?, 5000);
+    assert_eq!(crud::total::t_all(&conn, "rel_pairs", None, None)?, 5000);
+    assert!(batch_statements * 100 < pairs_statements);
+
+    Ok(())
+}
+
+#[test]
+fn test_self_referential_nn_bonds() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute_batch(
+        "CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+         CREATE TABLE rel_follow (follower_id INTEGER, followee_id INTEGER, PRIMARY KEY (follower_id, followee_id));
+         INSERT INTO user (id, name) VALUES (1, 'Ann'), (2, 'Bob'), (3, 'Cat');",
+    )?;
+    let user_rep = TblRep::new(
+        "user",
+        "id",
+        &[("id", v_int(0)), ("name", v_txt(""))],
+        &["name"],
+    )?;
+    let follows_nn = NnWrap::new(
+        &user_rep,
+        &user_rep,
+        ("rel_follow", "follower_id", "followee_id"),
+    );
+    let ids_of = |records: Vec<HashMap<String, rusqlite::types::Value>>| {
+        let mut ids = records
+            .iter()
+            .map(|record| record["id"].clone())
+            .collect::<Vec<_>>();
+        ids.sort_by_key(|id| format!("{:?}", id));
+        ids
+    };
+
+    follows_nn.link(&conn, &[v_int(1), v_int(2)], &[v_int(3)])?;
+    follows_nn.link(&conn, &[v_int(3)], &[v_int(1)])?;
+
+    let followers_of_cat = follows_nn.peers_of_t1(&conn, &[v_int(3)], None, None)?;
+    assert_eq!(ids_of(followers_of_cat), [v_int(1), v_int(2)]);
+    let followees_of_cat = follows_nn.peers_of_t2(&conn, &[v_int(3)], None, None)?;
+    assert_eq!(ids_of(followees_of_cat), [v_int(1)]);
+    assert_eq!(
+        follows_nn.count_peers_of_t2(&conn, &[v_int(1)], None, None)?,
+        1
+    );
+
+    let moved = follows_nn.relink_t2(&conn, &v_int(3), &v_int(2))?;
+    assert_eq!(moved, 2);
+    let followers_of_bob = follows_nn.peers_of_t1(&conn, &[v_int(2)], None, None)?;
+    assert_eq!(ids_of(followers_of_bob), [v_int(1), v_int(2)]);
+    assert_eq!(
+        ids_of(follows_nn.peers_of_t2(&conn, &[v_int(3)], None, None)?),
+        [v_int(1)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_list_parents_with_kid_count() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let (artist_rep, song_rep, album_rep) = get_infos()?;
+    let (artist_songs_n1, _) = get_wraps((&artist_rep, &song_rep, &album_rep))?;
+    artist_rep.insert(
+        &conn,
+        &HashMap::from([
+            ("id".to_string(), v_int(9)),
+            ("name".to_string(), v_txt("Newcomer")),
+        ]),
+        true,
+    )?;
+
+    let artists = artist_songs_n1.list_parents_with_kid_count(&conn, None, None)?;
+    assert_eq!(artists.len() as i64, artist_rep.count(&conn, None, None)?);
+    for artist in &artists {
+        let expected = artist_songs_n1.count_kids(&conn, &[artist["id"].clone()], None, None)?;
+        assert_eq!(artist["song_count"], v_int(expected));
+        assert!(artist.contains_key("name"));
+    }
+
+    let artists = artist_songs_n1.list_parents_with_kid_count(
+        &conn,
+        Some(&["id"]),
+        Some(("id IN (?, ?)", &[v_int(5), v_int(9)])),
+    )?;
+    let mut counts = artists
+        .iter()
+        .map(|artist| (artist["id"].clone(), artist["song_count"].clone()))
+        .collect::<Vec<_>>();
+    counts.sort_by_key(|(id, _)| format!("{:?}", id));
+    assert_eq!(counts, [(v_int(5), v_int(2)), (v_int(9), v_int(0))]);
+    assert_eq!(artists[0].len(), 2);
+
+    Ok(())
+}
@@ -1,5 +1,12 @@
 use insta::assert_snapshot;
-use jankenstore::{crud::shift::val::v_txt, TblRep};
+use jankenstore::{
+    crud::{
+        shift::val::v_txt,
+        sql::{CmpOp, Filter},
+        verify,
+    },
+    TblRep,
+};
 
 use anyhow::Result;
 use rusqlite::{types, Connection};
@@ -81,7 +88,10 @@ fn test_create_or_update_tbl_rep() -> Result<()> {
         ("name".to_string(), types::Value::Text("test2".to_string())),
         ("count".to_string(), types::Value::Integer(6)),
     ]);
-    tbl_rep.upd_by_pk(&conn, &[v_txt("1")], &update_input, None)?;
+    let updated = tbl_rep.upd_by_pk(&conn, &[v_txt("1")], &update_input, None)?;
+    assert_eq!(updated, 1);
+    let updated = tbl_rep.upd_by_pk(&conn, &[v_txt("-1")], &update_input, None)?;
+    assert_eq!(updated, 0);
     let rows = tbl_rep.list_by_pk(&conn, &[v_txt("1")], None)?;
     let count = rows[0].get("count").unwrap();
     match count {
@@ -167,3 +177,707 @@ fn test_fetching_by_multiple_primary_keys() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_record_to_update_input() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER, memo TEXT)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    conn.execute(
+        "INSERT INTO test (id, name, count, memo) VALUES (1, 'test', NULL, 'extra')",
+        [],
+    )?;
+    let rows = tbl_rep.list_by_pk(&conn, &[v_txt("1")], None)?;
+    let mut input = tbl_rep.record_to_update_input(&rows[0]);
+    assert_eq!(input, HashMap::from([("name".to_string(), v_txt("test"))]));
+
+    input.insert("name".to_string(), v_txt("edited"));
+    tbl_rep.upd_by_pk(&conn, &[v_txt("1")], &input, None)?;
+    let rows = tbl_rep.list_by_pk_as::<TestEntity>(&conn, &[v_txt("1")], None)?;
+    assert_eq!(rows[0].name.clone().unwrap(), "edited");
+    assert_eq!(rows[0].count, None);
+
+    let mut tbl_rep = tbl_rep;
+    tbl_rep.set_allow_null(true);
+    conn.execute(
+        "INSERT INTO test (id, name, count, memo) VALUES (2, 'other', 5, NULL)",
+        [],
+    )?;
+    let rows = tbl_rep.list_by_pk(&conn, &[v_txt("1")], None)?;
+    let input = tbl_rep.record_to_update_input(&rows[0]);
+    assert_eq!(
+        input,
+        HashMap::from([
+            ("name".to_string(), v_txt("edited")),
+            ("count".to_string(), types::Value::Null),
+        ])
+    );
+    tbl_rep.upd_by_pk(&conn, &[v_txt("2")], &input, None)?;
+    let rows = tbl_rep.list_by_pk_as::<TestEntity>(&conn, &[v_txt("2")], None)?;
+    assert_eq!(rows[0].name.clone().unwrap(), "edited");
+    assert_eq!(rows[0].count, None);
+    Ok(())
+}
+
+#[test]
+fn test_insert_many() -> Result<()> {
+    let mut conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    let record = |id: i64, name: &str| {
+        HashMap::from([
+            ("id".to_string(), types::Value::Integer(id)),
+            ("name".to_string(), v_txt(name)),
+        ])
+    };
+
+    tbl_rep.insert_many(&conn, &[record(1, "test1"), record(2, "test2")], true)?;
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 2);
+
+    let invalid_record_err = tbl_rep
+        .insert_many(&conn, &[record(3, "test3"), record(4, "")], true)
+        .err()
+        .unwrap();
+    assert_eq!(
+        invalid_record_err.to_string(),
+        "(table: test) The record at index 1 cannot be inserted: The input requires the value of 'name'"
+    );
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 2);
+
+    let duplicated_err = tbl_rep
+        .insert_many(
+            &conn,
+            &[record(5, "test5"), record(6, "test6"), record(1, "again")],
+            true,
+        )
+        .err()
+        .unwrap();
+    assert!(duplicated_err
+        .to_string()
+        .starts_with("(table: test) The record at index 2 cannot be inserted"));
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 2);
+
+    let tx = conn.transaction()?;
+    tbl_rep.insert_many(&tx, &[record(7, "test7")], true)?;
+    assert_eq!(tbl_rep.count(&tx, None, None)?, 3);
+    tx.rollback()?;
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_upsert() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("name".to_string(), v_txt("test")),
+        ("count".to_string(), types::Value::Integer(5)),
+    ]);
+    tbl_rep.upsert(&conn, &input, true)?;
+    let rows = tbl_rep.list_by_pk_as::<TestEntity>(&conn, &[v_txt("1")], None)?;
+    assert_eq!(rows[0].name.clone().unwrap(), "test");
+    assert_eq!(rows[0].count.unwrap(), 5);
+
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("name".to_string(), v_txt("upserted")),
+    ]);
+    tbl_rep.upsert(&conn, &input, true)?;
+    let rows = tbl_rep.list_by_pk_as::<TestEntity>(&conn, &[v_txt("1")], None)?;
+    assert_eq!(rows[0].name.clone().unwrap(), "upserted");
+    assert_eq!(rows[0].count.unwrap(), 5);
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 1);
+
+    let input = HashMap::from([("name".to_string(), v_txt("no pk"))]);
+    let no_pk_err = tbl_rep.upsert(&conn, &input, true).err().unwrap();
+    assert_snapshot!(no_pk_err.to_string());
+
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("name".to_string(), v_txt("test")),
+        ("age".to_string(), types::Value::Integer(18)),
+    ]);
+    let unknown_key_err = tbl_rep.upsert(&conn, &input, true).err().unwrap();
+    assert_eq!(
+        unknown_key_err.to_string(),
+        "(table: test) The input has a key 'age' that is not allowed"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_insert_returning() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, memo TEXT DEFAULT 'none')",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("memo", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    let input = HashMap::from([("name".to_string(), v_txt("test"))]);
+    let created = jankenstore::crud::create::i_one_returning(&conn, "test", &input, None)?;
+    assert_eq!(created.get("id"), Some(&types::Value::Integer(1)));
+    assert_eq!(created.get("name"), Some(&v_txt("test")));
+    assert_eq!(created.get("memo"), Some(&v_txt("none")));
+
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(5)),
+        ("name".to_string(), v_txt("test5")),
+    ]);
+    let created = tbl_rep.insert_returning(&conn, &input, false)?;
+    assert_eq!(created.get("id"), Some(&types::Value::Integer(5)));
+    assert_eq!(created.get("memo"), Some(&v_txt("none")));
+
+    let input = HashMap::from([("id".to_string(), types::Value::Integer(6))]);
+    let err = tbl_rep.insert_returning(&conn, &input, true).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The input requires the value of 'name'"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_update_requiring_match() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let mut tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("name".to_string(), v_txt("test")),
+    ]);
+    tbl_rep.insert(&conn, &input, true)?;
+    let update_input = HashMap::from([("count".to_string(), types::Value::Integer(5))]);
+    let missing_filter = Filter::Cmp("id".to_string(), CmpOp::Eq, types::Value::Integer(9));
+
+    assert!(!tbl_rep.get_require_match());
+    assert_eq!(
+        tbl_rep.upd_by_pk(&conn, &[v_txt("9")], &update_input, None)?,
+        0
+    );
+    assert_eq!(
+        tbl_rep.upd_by_filter(&conn, &missing_filter, &update_input)?,
+        0
+    );
+
+    tbl_rep.set_require_match(true);
+    assert_eq!(
+        tbl_rep.upd_by_pk(&conn, &[v_txt("1")], &update_input, None)?,
+        1
+    );
+    let err = tbl_rep
+        .upd_by_pk(&conn, &[v_txt("9")], &update_input, None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) No record matches the update"
+    );
+    assert!(tbl_rep
+        .upd_by_filter(&conn, &missing_filter, &update_input)
+        .is_err());
+
+    let updates = [
+        (
+            v_txt("1"),
+            HashMap::from([("count".to_string(), types::Value::Integer(7))]),
+        ),
+        (v_txt("9"), update_input.clone()),
+    ];
+    assert!(tbl_rep.upd_each(&conn, &updates).is_err());
+    let rows = tbl_rep.list_by_pk_as::<TestEntity>(&conn, &[v_txt("1")], None)?;
+    assert_eq!(rows[0].count, Some(5));
+
+    Ok(())
+}
+
+#[test]
+fn test_update_to_null() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let mut tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("name".to_string(), v_txt("test")),
+    ]);
+    tbl_rep.insert(&conn, &input, true)?;
+
+    let update_input = HashMap::from([("count".to_string(), types::Value::Null)]);
+    assert!(!tbl_rep.get_allow_null());
+    let report = tbl_rep.report_update_issues(&update_input);
+    assert_eq!(report.type_mismatches, vec!["count"]);
+    let err = tbl_rep
+        .upd_by_pk(&conn, &[v_txt("1")], &update_input, None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The input's value type for 'count' must be something like Integer(2), but received Null"
+    );
+    let filter = Filter::Cmp("id".to_string(), CmpOp::Eq, types::Value::Integer(1));
+    assert!(tbl_rep
+        .upd_by_filter(&conn, &filter, &update_input)
+        .is_err());
+    assert!(tbl_rep.verify_update(&update_input).is_err());
+
+    tbl_rep.set_allow_null(true);
+    assert!(tbl_rep.report_update_issues(&update_input).is_clean());
+    tbl_rep.verify_update(&update_input)?;
+    let updated = tbl_rep.upd_by_pk(&conn, &[v_txt("1")], &update_input, None)?;
+    assert_eq!(updated, 1);
+    let rows = tbl_rep.list_by_pk_as::<TestEntity>(&conn, &[v_txt("1")], None)?;
+    assert_eq!(rows[0].count, None);
+
+    let update_input = HashMap::from([("name".to_string(), types::Value::Null)]);
+    let report = tbl_rep.report_update_issues(&update_input);
+    assert_eq!(report.type_mismatches, vec!["name"]);
+    assert!(tbl_rep
+        .upd_by_pk(&conn, &[v_txt("1")], &update_input, None)
+        .is_err());
+
+    let insert_input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(2)),
+        ("name".to_string(), v_txt("test2")),
+        ("count".to_string(), types::Value::Null),
+    ]);
+    let err = tbl_rep.insert(&conn, &insert_input, true).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The input's value type for 'count' must be something like Integer(2), but received Null"
+    );
+
+    let update_input = HashMap::from([("count".to_string(), types::Value::Null)]);
+    let (defaults, required_fields) = (tbl_rep.get_defaults(), tbl_rep.get_required_fields());
+    assert!(verify::verify_required_fields_for_write_ops(
+        &update_input,
+        "test",
+        required_fields,
+        defaults,
+        false
+    )
+    .is_err());
+    verify::verify_required_fields_for_write_ops_with(
+        &update_input,
+        "test",
+        required_fields,
+        defaults,
+        (false, true),
+    )?;
+    let report = verify::report_write_input_issues(&update_input, required_fields, defaults, false);
+    assert_eq!(report.type_mismatches, vec!["count"]);
+    assert!(verify::report_write_input_issues_with(
+        &update_input,
+        required_fields,
+        defaults,
+        (false, true)
+    )
+    .is_clean());
+
+    Ok(())
+}
+
+#[test]
+fn test_update_by_filter() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    for id in 1..=4 {
+        let input = HashMap::from([
+            ("id".to_string(), types::Value::Integer(id)),
+            ("name".to_string(), v_txt(&format!("test{}", id))),
+            ("count".to_string(), types::Value::Integer(id * 10)),
+        ]);
+        tbl_rep.insert(&conn, &input, true)?;
+    }
+
+    let filter = Filter::Or(vec![
+        Filter::Cmp("count".to_string(), CmpOp::Lt, types::Value::Integer(20)),
+        Filter::Cmp("name".to_string(), CmpOp::Eq, v_txt("test4")),
+    ]);
+    let input = HashMap::from([("name".to_string(), v_txt("archived"))]);
+    let updated = tbl_rep.upd_by_filter(&conn, &filter, &input)?;
+    assert_eq!(updated, 2);
+    let archived = tbl_rep.list_as::<TestEntity>(
+        &conn,
+        Some(("name = ?", &[v_txt("archived")])),
+        (false, None),
+    )?;
+    assert_eq!(
+        archived
+            .iter()
+            .map(|row| row.id.unwrap())
+            .collect::<Vec<_>>(),
+        vec![1, 4]
+    );
+
+    let empty_filter_err = tbl_rep
+        .upd_by_filter(&conn, &Filter::And(vec![]), &input)
+        .err()
+        .unwrap();
+    assert_eq!(
+        empty_filter_err.to_string(),
+        "(table: test) At least 1 condition is required for the filter group 'AND'"
+    );
+
+    let input = HashMap::from([("name".to_string(), v_txt(""))]);
+    assert!(tbl_rep.upd_by_filter(&conn, &filter, &input).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_insert_or_ignore() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    let fixtures = [(1, "one"), (2, "two")].map(|(id, name)| {
+        HashMap::from([
+            ("id".to_string(), types::Value::Integer(id)),
+            ("name".to_string(), v_txt(name)),
+        ])
+    });
+    for fixture in &fixtures {
+        assert_eq!(tbl_rep.insert_or_ignore(&conn, fixture, true)?, 1);
+    }
+    let update_input = HashMap::from([("count".to_string(), types::Value::Integer(9))]);
+    tbl_rep.upd_by_pk(&conn, &[v_txt("1")], &update_input, None)?;
+    for fixture in &fixtures {
+        assert_eq!(tbl_rep.insert_or_ignore(&conn, fixture, true)?, 0);
+    }
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 2);
+    let rows = tbl_rep.list_by_pk_as::<TestEntity>(&conn, &[v_txt("1")], None)?;
+    assert_eq!(rows[0].count.unwrap(), 9);
+
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("name".to_string(), v_txt("")),
+    ]);
+    let invalid_err = tbl_rep.insert_or_ignore(&conn, &input, true).err().unwrap();
+    assert_eq!(
+        invalid_err.to_string(),
+        "(table: test) The input requires the value of 'name'"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_update_each() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    for id in 1..=3 {
+        let input = HashMap::from([
+            ("id".to_string(), types::Value::Integer(id)),
+            ("name".to_string(), v_txt(&format!("test{}", id))),
+            ("count".to_string(), types::Value::Integer(id)),
+        ]);
+        tbl_rep.insert(&conn, &input, true)?;
+    }
+    let counts = |conn: &Connection| -> Result<Vec<i64>> {
+        let rows = tbl_rep.list_as::<TestEntity>(conn, None, (false, None))?;
+        Ok(rows.iter().map(|row| row.count.unwrap()).collect())
+    };
+    let count_input =
+        |count: i64| HashMap::from([("count".to_string(), types::Value::Integer(count))]);
+
+    let updated = tbl_rep.upd_each(
+        &conn,
+        &[
+            (types::Value::Integer(1), count_input(3)),
+            (types::Value::Integer(3), count_input(1)),
+            (types::Value::Integer(9), count_input(9)),
+        ],
+    )?;
+    assert_eq!(updated, 2);
+    assert_eq!(counts(&conn)?, [3, 2, 1]);
+
+    let result = tbl_rep.upd_each(
+        &conn,
+        &[
+            (types::Value::Integer(1), count_input(10)),
+            (
+                types::Value::Integer(2),
+                HashMap::from([("count".to_string(), v_txt("twenty"))]),
+            ),
+        ],
+    );
+    assert!(result.is_err());
+    assert_eq!(counts(&conn)?, [3, 2, 1]);
+
+    Ok(())
+}
+
+#[test]
+fn test_insert_with_new_pk() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute_batch(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+         CREATE TABLE doc (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+         CREATE TABLE tag (id REAL PRIMARY KEY, name TEXT NOT NULL);",
+    )?;
+    let int_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    let input = HashMap::from([("name".to_string(), v_txt("first"))]);
+    assert_eq!(
+        int_rep.insert_with_new_pk(&conn, &input, true)?,
+        types::Value::Integer(1)
+    );
+    assert_eq!(
+        int_rep.insert_with_new_pk(&conn, &input, true)?,
+        types::Value::Integer(2)
+    );
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(9)),
+        ("name".to_string(), v_txt("given")),
+    ]);
+    assert_eq!(
+        int_rep.insert_with_new_pk(&conn, &input, true)?,
+        types::Value::Integer(9)
+    );
+    let err = int_rep
+        .insert_with_new_pk(&conn, &HashMap::new(), false)
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "(table: test) The input has no items");
+
+    let doc_rep = TblRep::new(
+        "doc",
+        "id",
+        &[
+            ("id", types::Value::Text("".to_string())),
+            ("name", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    let input = HashMap::from([("name".to_string(), v_txt("readme"))]);
+    let first_id = doc_rep.insert_with_new_pk(&conn, &input, true)?;
+    let second_id = doc_rep.insert_with_new_pk(&conn, &input, true)?;
+    assert_ne!(first_id, second_id);
+    let types::Value::Text(uuid) = &first_id else {
+        panic!("expected a text primary key, got {:?}", first_id);
+    };
+    assert_eq!(uuid.len(), 36);
+    assert_eq!(
+        uuid.split('-').map(|part| part.len()).collect::<Vec<_>>(),
+        [8, 4, 4, 4, 12]
+    );
+    assert_eq!(&uuid[14..15], "4");
+    assert!(["8", "9", "a", "b"].contains(&&uuid[19..20]));
+    assert_eq!(doc_rep.list_by_pk(&conn, &[first_id], None)?.len(), 1);
+
+    let tag_rep = TblRep::new(
+        "tag",
+        "id",
+        &[
+            ("id", types::Value::Real(0.0)),
+            ("name", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    let err = tag_rep
+        .insert_with_new_pk(&conn, &input, true)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: tag) The primary key 'id' can only be generated for integer or text types, but its default value is Some(Real(0.0))"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_write_empty_as_null() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, memo TEXT)",
+        [],
+    )?;
+    let mut tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("memo", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    let null_memo_count = |conn: &Connection| -> Result<i64> {
+        jankenstore::crud::total::t_all(conn, "test", None, Some(("memo IS NULL", &[])))
+    };
+    let input_of = |id: i64, name: &str, memo: &str| {
+        HashMap::from([
+            ("id".to_string(), types::Value::Integer(id)),
+            ("name".to_string(), v_txt(name)),
+            ("memo".to_string(), v_txt(memo)),
+        ])
+    };
+
+    tbl_rep.insert(&conn, &input_of(1, "kept", ""), true)?;
+    assert_eq!(null_memo_count(&conn)?, 0);
+    assert!(!tbl_rep.get_empty_as_null());
+
+    tbl_rep.set_empty_as_null(true);
+    tbl_rep.insert(&conn, &input_of(2, "cleared", " "), true)?;
+    tbl_rep.insert_many(
+        &conn,
+        &[input_of(3, "many", ""), input_of(4, "many", "note")],
+        true,
+    )?;
+    assert_eq!(null_memo_count(&conn)?, 2);
+    let created = tbl_rep.insert_returning(
+        &conn,
+        &HashMap::from([
+            ("id".to_string(), types::Value::Integer(5)),
+            ("name".to_string(), v_txt("absent")),
+        ]),
+        true,
+    )?;
+    assert_eq!(created.get("memo"), Some(&types::Value::Null));
+
+    let updated = tbl_rep.upd_by_pk(
+        &conn,
+        &[types::Value::Integer(1)],
+        &HashMap::from([("memo".to_string(), v_txt(""))]),
+        None,
+    )?;
+    assert_eq!(updated, 1);
+    assert_eq!(null_memo_count(&conn)?, 4);
+
+    let err = tbl_rep
+        .insert(&conn, &input_of(6, "", "note"), true)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The input requires the value of 'name'"
+    );
+    let err = tbl_rep
+        .insert_many(&conn, &[input_of(6, "ok", ""), input_of(7, "", "")], true)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The record at index 1 cannot be inserted: The input requires the value of 'name'"
+    );
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 5);
+
+    Ok(())
+}
@@ -1,4 +1,10 @@
-use jankenstore::{crud::shift::val::v_txt, TblRep};
+use jankenstore::{
+    crud::{
+        shift::val::v_txt,
+        sql::{CmpOp, Filter},
+    },
+    TblRep,
+};
 
 use anyhow::Result;
 use rusqlite::{types, Connection};
@@ -43,7 +49,10 @@ fn test_hard_delete() -> Result<()> {
 
     let rows = tbl_rep.list(&conn, None, (false, None)).unwrap();
     assert_eq!(rows.len(), 3);
-    tbl_rep.del_by_pk(&conn, &["1"].map(v_txt), None).unwrap();
+    let deleted = tbl_rep.del_by_pk(&conn, &["1"].map(v_txt), None).unwrap();
+    assert_eq!(deleted, 1);
+    let deleted = tbl_rep.del_by_pk(&conn, &["1"].map(v_txt), None).unwrap();
+    assert_eq!(deleted, 0);
     let rows = tbl_rep.list_by_pk(&conn, &["1"].map(v_txt), None)?;
     let row = rows.first();
     assert_eq!(row, None);
@@ -82,3 +91,110 @@ fn test_hard_delete() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_delete_by_filter() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+    for id in 1..=5 {
+        let input = HashMap::from([
+            ("id".to_string(), types::Value::Integer(id)),
+            (
+                "name".to_string(),
+                types::Value::Text(format!("test{}", id)),
+            ),
+            ("count".to_string(), types::Value::Integer(id)),
+        ]);
+        tbl_rep.insert(&conn, &input, true)?;
+    }
+
+    let filter = Filter::Cmp("count".to_string(), CmpOp::Gt, types::Value::Integer(2));
+    let too_many_err = tbl_rep
+        .del_by_filter(&conn, &filter, Some(2))
+        .err()
+        .unwrap();
+    assert_eq!(
+        too_many_err.to_string(),
+        "(table: test) 3 records match the condition, which exceeds the limit of 2"
+    );
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 5);
+
+    assert_eq!(tbl_rep.del_by_filter(&conn, &filter, Some(3))?, 3);
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 2);
+
+    let filter = Filter::Or(vec![
+        Filter::Cmp("name".to_string(), CmpOp::Eq, v_txt("test1")),
+        Filter::Cmp("count".to_string(), CmpOp::Eq, types::Value::Integer(2)),
+    ]);
+    assert_eq!(tbl_rep.del_by_filter(&conn, &filter, None)?, 2);
+
+    let empty_filter_err = tbl_rep
+        .del_by_filter(&conn, &Filter::Or(vec![]), None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        empty_filter_err.to_string(),
+        "(table: test) At least 1 condition is required for the filter group 'OR'"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_hard_delete_by_typed_pks() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute_batch(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+         INSERT INTO test (id, name) VALUES (1, 'a'), (2, 'b'), (3, 'c');
+         CREATE TABLE code (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+         INSERT INTO code (id, name) VALUES ('7', 'seven'), ('007', 'agent');",
+    )?;
+    let int_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    let deleted = int_rep.del_by_pk(
+        &conn,
+        &[types::Value::Integer(1), types::Value::Integer(3)],
+        None,
+    )?;
+    assert_eq!(deleted, 2);
+    let rows = int_rep.list(&conn, None, (false, None))?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["id"], types::Value::Integer(2));
+
+    let text_rep = TblRep::new(
+        "code",
+        "id",
+        &[
+            ("id", types::Value::Text("".to_string())),
+            ("name", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    let deleted = text_rep.del_by_pk(&conn, &[v_txt("007")], None)?;
+    assert_eq!(deleted, 1);
+    let rows = text_rep.list(&conn, None, (false, None))?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["name"], v_txt("seven"));
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use jankenstore::{
+    crud::total,
+    migrate::{run_migrations, Migration, MIGRATION_TABLE},
+};
+
+use insta::assert_snapshot;
+use rusqlite::Connection;
+
+const MIGRATIONS: [Migration; 2] = [
+    Migration {
+        version: 1,
+        up_sql: "CREATE TABLE song (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+    },
+    Migration {
+        version: 2,
+        up_sql: "ALTER TABLE song ADD COLUMN memo TEXT DEFAULT ''; CREATE INDEX song_name ON song (name);",
+    },
+];
+
+#[test]
+fn test_run_migrations() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    assert_eq!(run_migrations(&conn, &MIGRATIONS[..1])?, 1);
+    assert_eq!(run_migrations(&conn, &MIGRATIONS)?, 1);
+    assert_eq!(run_migrations(&conn, &MIGRATIONS)?, 0);
+    assert_eq!(total::t_all(&conn, MIGRATION_TABLE, None, None)?, 2);
+    conn.execute("INSERT INTO song (name, memo) VALUES ('test', '90s')", [])?;
+
+    let newer_db_err = run_migrations(&conn, &MIGRATIONS[..1]).err().unwrap();
+    assert_snapshot!(newer_db_err.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_migrations() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    let out_of_order_err = run_migrations(&conn, &[MIGRATIONS[1], MIGRATIONS[0]])
+        .err()
+        .unwrap();
+    assert_snapshot!(out_of_order_err.to_string());
+
+    let broken = [
+        MIGRATIONS[0],
+        Migration {
+            version: 2,
+            up_sql:
+                "ALTER TABLE song ADD COLUMN memo TEXT; ALTER TABLE missing ADD COLUMN memo TEXT;",
+        },
+    ];
+    let broken_err = run_migrations(&conn, &broken).err().unwrap();
+    assert_snapshot!(broken_err.to_string());
+    assert_eq!(total::t_all(&conn, MIGRATION_TABLE, None, None)?, 1);
+    assert_eq!(run_migrations(&conn, &MIGRATIONS)?, 1);
+
+    conn.execute(
+        &format!("INSERT INTO {} (version) VALUES (4)", MIGRATION_TABLE),
+        [],
+    )?;
+    let gap_err = run_migrations(&conn, &MIGRATIONS).err().unwrap();
+    assert_snapshot!(gap_err.to_string());
+
+    Ok(())
+}
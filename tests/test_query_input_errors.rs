@@ -243,3 +243,158 @@ fn test_invalid_inputs() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_input_issue_reports() -> Result<()> {
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("title", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name", "title"],
+    )?;
+
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Text("abc".to_string())),
+        ("count".to_string(), types::Value::Real(2.0)),
+        ("age".to_string(), types::Value::Integer(18)),
+        (
+            "nickname".to_string(),
+            types::Value::Text("bob".to_string()),
+        ),
+    ]);
+    let report = tbl_rep.report_insert_issues(&input, false);
+    assert!(!report.is_clean());
+    assert_eq!(report.unknown_keys, vec!["age", "nickname"]);
+    assert_eq!(report.type_mismatches, vec!["count", "id"]);
+    assert_eq!(report.missing_required, vec!["name", "title"]);
+
+    let input = HashMap::from([("name".to_string(), types::Value::Text("test".to_string()))]);
+    let report = tbl_rep.report_insert_issues(&input, true);
+    assert_eq!(report.missing_required, vec!["title"]);
+
+    let input = HashMap::from([
+        ("name".to_string(), types::Value::Text("test".to_string())),
+        ("title".to_string(), types::Value::Text("".to_string())),
+    ]);
+    let report = tbl_rep.report_update_issues(&input);
+    assert_eq!(report.missing_required, vec!["title"]);
+
+    let input = HashMap::from([("count".to_string(), types::Value::Integer(3))]);
+    let report = tbl_rep.report_update_issues(&input);
+    assert!(report.is_clean());
+    assert!(!report.is_empty_input);
+
+    let input = HashMap::new();
+    let report = tbl_rep.report_update_issues(&input);
+    assert!(report.is_empty_input);
+    assert!(!report.is_clean());
+    let err = tbl_rep.verify_update(&input).err().unwrap();
+    assert_eq!(err.to_string(), "(table: test) The input has no items");
+
+    let report = tbl_rep.report_insert_issues(&input, false);
+    assert!(report.is_empty_input);
+    assert_eq!(report.missing_required, vec!["id", "name", "title"]);
+    let report = tbl_rep.report_insert_issues(&input, true);
+    assert!(!report.is_empty_input);
+
+    Ok(())
+}
+
+#[test]
+fn test_verify_only() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, count INTEGER DEFAULT 2)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("count", types::Value::Integer(2)),
+        ],
+        &["name"],
+    )?;
+
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("name".to_string(), types::Value::Text("test".to_string())),
+    ]);
+    tbl_rep.verify_insert(&input, true)?;
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 0);
+
+    let input = HashMap::from([
+        ("id".to_string(), types::Value::Integer(1)),
+        ("count".to_string(), types::Value::Real(2.0)),
+    ]);
+    let verify_err = tbl_rep.verify_insert(&input, true).err().unwrap();
+    let insert_err = tbl_rep.insert(&conn, &input, true).err().unwrap();
+    assert_eq!(verify_err.to_string(), insert_err.to_string());
+
+    let input = HashMap::from([("id".to_string(), types::Value::Integer(1))]);
+    let verify_err = tbl_rep.verify_insert(&input, false).err().unwrap();
+    let insert_err = tbl_rep.insert(&conn, &input, false).err().unwrap();
+    assert_eq!(verify_err.to_string(), insert_err.to_string());
+
+    let input = HashMap::from([("count".to_string(), types::Value::Integer(3))]);
+    tbl_rep.verify_update(&input)?;
+
+    let input = HashMap::from([("name".to_string(), types::Value::Text("".to_string()))]);
+    let verify_err = tbl_rep.verify_update(&input).err().unwrap();
+    let update_err = tbl_rep
+        .upd_by_pk(&conn, &[types::Value::Integer(1)], &input, None)
+        .err()
+        .unwrap();
+    assert_eq!(verify_err.to_string(), update_err.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_type_mismatch_error_details() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE song (id INTEGER PRIMARY KEY, name TEXT NOT NULL, artist_id INTEGER)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("artist_id", types::Value::Integer(0)),
+        ],
+        &["name"],
+    )?;
+    let input = HashMap::from([
+        ("name".to_string(), types::Value::Text("test".to_string())),
+        (
+            "artist_id".to_string(),
+            types::Value::Text("abc".to_string()),
+        ),
+    ]);
+    let err = tbl_rep
+        .insert(&conn, &input, true)
+        .err()
+        .unwrap()
+        .to_string();
+    for piece in [
+        "(table: song)",
+        "'artist_id'",
+        "Integer(0)",
+        "Text(\"abc\")",
+    ] {
+        assert!(err.contains(piece), "'{}' is missing in: {}", piece, err);
+    }
+    let verify_err = tbl_rep.verify_insert(&input, true).err().unwrap();
+    assert_eq!(verify_err.to_string(), err);
+    Ok(())
+}
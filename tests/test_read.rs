@@ -1,8 +1,17 @@
 mod helpers;
 use helpers::initialize_db;
 
-use jankenstore::crud;
-use rusqlite::Connection;
+use insta::assert_snapshot;
+use jankenstore::{
+    crud::{
+        self,
+        shift::val::{v_int, v_txt},
+        sql::{CmpOp, Filter, NullCheck},
+    },
+    TblRep,
+};
+use rusqlite::{types, Connection};
+use std::collections::HashMap;
 
 #[test]
 fn test_count() -> anyhow::Result<()> {
@@ -17,3 +26,910 @@ fn test_count() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_null_safe_comparison() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    conn.execute("UPDATE song SET memo = NULL WHERE id = 1", [])?;
+
+    let (clause, params) = crud::sql::distinct_from("memo", &v_txt("60s"), None)?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 4);
+    assert!(songs.iter().any(|song| song.get("id") == Some(&v_int(1))));
+
+    let (clause, params) = crud::sql::not_distinct_from("memo", &types::Value::Null, None)?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0].get("id"), Some(&v_int(1)));
+
+    let (clause, params) = crud::sql::distinct_from("memo", &types::Value::Null, None)?;
+    let total = crud::total::t_all(&conn, "song", None, Some((&clause, &params)))?;
+    assert_eq!(total, 5);
+
+    let columns = HashMap::from([("memo".to_string(), v_txt(""))]);
+    let (clause, params) = crud::sql::not_distinct_from("memo", &v_txt("60s"), Some(&columns))?;
+    let total = crud::total::t_all(&conn, "song", None, Some((&clause, &params)))?;
+    assert_eq!(total, 2);
+
+    let unknown_err = crud::sql::distinct_from("genre", &v_txt("pop"), Some(&columns))
+        .err()
+        .unwrap();
+    assert_eq!(
+        unknown_err.to_string(),
+        "The column 'genre' cannot be used for filtering"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_histogram() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+
+    let buckets = song_rep.histogram(&conn, "id", (0.0, 6.0, 3), None)?;
+    assert_eq!(buckets, HashMap::from([(0, 1), (1, 2), (2, 3)]));
+
+    let buckets = song_rep.histogram(
+        &conn,
+        "id",
+        (0.0, 6.0, 3),
+        Some(("memo = ?", &[v_txt("60s")])),
+    )?;
+    assert_eq!(buckets, HashMap::from([(1, 2)]));
+
+    let buckets = song_rep.histogram(
+        &conn,
+        "id",
+        (2.0, 4.0, 2),
+        Some(("id = ? OR memo = ?", &[v_int(3), v_txt("30s")])),
+    )?;
+    assert_eq!(buckets, HashMap::from([(1, 1)]));
+
+    let not_numeric_err = song_rep
+        .histogram(&conn, "name", (0.0, 6.0, 3), None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        not_numeric_err.to_string(),
+        "(table: song) The column 'name' is not a numeric column"
+    );
+
+    let no_bucket_err = song_rep
+        .histogram(&conn, "id", (0.0, 6.0, 0), None)
+        .err()
+        .unwrap();
+    assert_snapshot!(no_bucket_err.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_null_checks() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    conn.execute("UPDATE song SET artist_id = NULL WHERE id IN (1, 2)", [])?;
+    conn.execute("UPDATE song SET memo = NULL WHERE id = 2", [])?;
+
+    let (clause, params) = crud::sql::null_checks(&[("artist_id", NullCheck::IsNull)], None)?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 2);
+
+    let (clause, params) = crud::sql::null_checks(
+        &[
+            ("artist_id", NullCheck::IsNull),
+            ("memo", NullCheck::IsNotNull),
+        ],
+        None,
+    )?;
+    let (clause, params) = crud::sql::merge_q_configs(
+        Some((&clause, &params)),
+        Some(("id > ?", &[v_int(0)])),
+        "AND",
+    )?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0].get("id"), Some(&v_int(1)));
+
+    let no_check_err = crud::sql::null_checks(&[], None).err().unwrap();
+    assert_snapshot!(no_check_err.to_string());
+
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+    let (clause, params) = song_rep.null_checks_q_config(&[("memo", NullCheck::IsNull)])?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0].get("id"), Some(&v_int(2)));
+
+    let unknown_err = song_rep
+        .null_checks_q_config(&[("memo", NullCheck::IsNull), ("genre", NullCheck::IsNotNull)])
+        .err()
+        .unwrap();
+    assert_eq!(
+        unknown_err.to_string(),
+        "(table: song) The column 'genre' cannot be used for filtering"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_range_condition() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+
+    let (clause, params) = crud::sql::between("id", (&v_int(2), &v_int(4)), None)?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 3);
+
+    let (clause, params) = crud::sql::between("memo", (&v_txt("60s"), &v_txt("80s")), None)?;
+    let total = crud::total::t_all(&conn, "song", None, Some((&clause, &params)))?;
+    assert_eq!(total, 3);
+
+    let inverted_err = crud::sql::between("id", (&v_int(4), &v_int(2)), None)
+        .err()
+        .unwrap();
+    assert_snapshot!(inverted_err.to_string());
+
+    let mismatch_err = crud::sql::between("id", (&v_int(2), &v_txt("4")), None)
+        .err()
+        .unwrap();
+    assert_snapshot!(mismatch_err.to_string());
+
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+    let (clause, params) = song_rep.between_q_config("id", (&v_int(2), &v_int(4)))?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 3);
+
+    let unknown_err = song_rep
+        .between_q_config("genre", (&v_txt("a"), &v_txt("z")))
+        .err()
+        .unwrap();
+    assert_eq!(
+        unknown_err.to_string(),
+        "(table: song) The column 'genre' cannot be used for filtering"
+    );
+
+    let column_mismatch_err = song_rep
+        .between_q_config("id", (&v_txt("2"), &v_txt("4")))
+        .err()
+        .unwrap();
+    assert_eq!(
+        column_mismatch_err.to_string(),
+        "(table: song) The range of 'id' requires bounds of the same type as the column, something like Integer(0), but received Text(\"2\") and Text(\"4\")"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_padded_pk_reads() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+
+    let (clause, params) = crud::sql::in_them_padded("id", &[1, 2, 3].map(v_int), 500);
+    assert_eq!(clause, "id IN (?, ?, ?, ?)");
+    assert_eq!(params, [1, 2, 3, 3].map(v_int));
+
+    let (_, params) = crud::sql::in_them_padded("id", &[1, 2, 3, 4, 5].map(v_int), 6);
+    assert_eq!(params, [1, 2, 3, 4, 5, 5].map(v_int));
+    let (_, params) = crud::sql::in_them_padded("id", &[1, 2, 3].map(v_int), 2);
+    assert_eq!(params, [1, 2, 3].map(v_int));
+
+    let chunks =
+        crud::sql::in_them_chunks("id", &(1..=600).map(v_int).collect::<Vec<_>>(), 500, true);
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].1.len(), 500);
+    assert_eq!(chunks[1].1.len(), 128);
+
+    let chunks = crud::sql::in_them_chunks("id", &[1, 2, 1, 3, 2].map(v_int), 500, false);
+    assert_eq!(
+        chunks,
+        vec![("id IN (?, ?, ?)".to_string(), [1, 2, 3].map(v_int).to_vec())]
+    );
+
+    let values = [
+        v_int(1),
+        v_txt("1"),
+        types::Value::Real(0.0),
+        types::Value::Real(-0.0),
+        types::Value::Null,
+        v_int(1),
+        types::Value::Null,
+    ];
+    assert_eq!(
+        crud::sql::dedup_values(&values),
+        [
+            v_int(1),
+            v_txt("1"),
+            types::Value::Real(0.0),
+            types::Value::Null
+        ]
+    );
+
+    let (clause, params) = crud::sql::in_them_padded("id", &[], 500);
+    assert_eq!(clause, "id IN ()");
+    assert!(params.is_empty());
+
+    for pk in 1..=6 {
+        let songs = crud::fetch::f_by_pk(&conn, "song", ("id", &[v_int(pk)]), None, None)?;
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].get("id"), Some(&v_int(pk)));
+        let songs = crud::fetch::f_by_pk_cached(&conn, "song", ("id", &[v_int(pk)]), None, None)?;
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].get("id"), Some(&v_int(pk)));
+    }
+
+    let songs = crud::fetch::f_by_pk(&conn, "song", ("id", &[1, 2, 3].map(v_int)), None, None)?;
+    assert_eq!(songs.len(), 3);
+    let pk_values = [1, 2, 3].map(v_int);
+    let songs = crud::fetch::f_by_pk_cached(&conn, "song", ("id", &pk_values), None, None)?;
+    assert_eq!(songs.len(), 3);
+
+    Ok(())
+}
+
+// a rough benchmark of repeated single-key reads, run it by
+// `cargo test --release --test test_read -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_repeated_pk_reads() -> anyhow::Result<()> {
+    use std::time::Instant;
+
+    let conn = Connection::open_in_memory()?;
+    conn.execute("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT)", [])?;
+    for id in 1..=1000 {
+        conn.execute(
+            "INSERT INTO test (id, name) VALUES (?, ?)",
+            (id, format!("test{}", id)),
+        )?;
+    }
+    let key_lists = (1..=10000)
+        .map(|i| {
+            (0..(i % 7 + 1))
+                .map(|j| v_int((i + j) % 1000 + 1))
+                .collect()
+        })
+        .collect::<Vec<Vec<_>>>();
+
+    let started = Instant::now();
+    for pk_values in &key_lists {
+        crud::fetch::f_by_pk(&conn, "test", ("id", pk_values), None, None)?;
+    }
+    println!("f_by_pk: {:?}", started.elapsed());
+
+    let started = Instant::now();
+    for pk_values in &key_lists {
+        crud::fetch::f_by_pk_cached(&conn, "test", ("id", pk_values), None, None)?;
+    }
+    println!("f_by_pk_cached: {:?}", started.elapsed());
+
+    let started = Instant::now();
+    for pk in 1..=10000 {
+        crud::fetch::f_by_pk(&conn, "test", ("id", &[v_int(pk % 1000 + 1)]), None, None)?;
+    }
+    println!("single key f_by_pk: {:?}", started.elapsed());
+
+    Ok(())
+}
+
+#[test]
+fn test_read_each() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+
+    let mut names = vec![];
+    song_rep.each(&conn, None, (false, Some(&["name"])), |record| {
+        assert_eq!(record.len(), 1);
+        names.push(record["name"].clone());
+        Ok(())
+    })?;
+    let all_names = song_rep
+        .list(&conn, None, (false, Some(&["name"])))?
+        .into_iter()
+        .map(|record| record["name"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, all_names);
+
+    let mut visited = 0;
+    let err = crud::fetch::f_each(&conn, "song", None, (false, None), |_| {
+        visited += 1;
+        if visited == 2 {
+            return Err(anyhow::anyhow!("stop at the second record"));
+        }
+        Ok(())
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err.to_string(), "stop at the second record");
+    assert_eq!(visited, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_distinct_values() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    conn.execute("UPDATE song SET memo = NULL WHERE id = 1", [])?;
+    conn.execute("UPDATE song SET memo = '' WHERE id = 6", [])?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+
+    let memos = song_rep.distinct_values(&conn, "memo", None, (true, true))?;
+    assert_eq!(memos, ["60s", "80s", "90s"].map(v_txt));
+
+    let memos = song_rep.distinct_values(&conn, "memo", None, (true, false))?;
+    assert_eq!(memos.len(), 5);
+    assert_eq!(memos[0], types::Value::Null);
+
+    let artist_ids = song_rep.distinct_values(
+        &conn,
+        "artist_id",
+        Some(("id > ?", &[v_int(3)])),
+        (true, true),
+    )?;
+    assert_eq!(artist_ids, [4, 5].map(v_int));
+
+    let err = song_rep
+        .distinct_values(&conn, "genre", None, (true, true))
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: song) The column 'genre' does not exist"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_filter_tree() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+
+    let filter = Filter::Or(vec![
+        Filter::Cmp("memo".to_string(), CmpOp::Eq, v_txt("60s")),
+        Filter::And(vec![
+            Filter::Cmp("artist_id".to_string(), CmpOp::Eq, v_int(5)),
+            Filter::Cmp("name".to_string(), CmpOp::Like, v_txt("%Go%")),
+        ]),
+    ]);
+    let (clause, params) = song_rep.filter_q_config(&filter)?;
+    assert_eq!(clause, "(memo = ? OR (artist_id = ? AND name LIKE ?))");
+    let songs = song_rep.list_by_pk(&conn, &[2, 3, 4, 6].map(v_int), Some((&clause, &params)))?;
+    let mut ids = songs
+        .iter()
+        .map(|song| song["id"].clone())
+        .collect::<Vec<_>>();
+    ids.sort_by_key(|id| match id {
+        types::Value::Integer(id) => *id,
+        _ => 0,
+    });
+    assert_eq!(ids, [2, 3, 6].map(v_int));
+
+    conn.execute("UPDATE song SET memo = NULL WHERE id IN (1, 4)", [])?;
+    let null_memo_count = |op: CmpOp| -> anyhow::Result<i64> {
+        let (clause, params) =
+            song_rep.filter_q_config(&Filter::Cmp("memo".to_string(), op, types::Value::Null))?;
+        song_rep.count(&conn, None, Some((&clause, &params)))
+    };
+    assert_eq!(null_memo_count(CmpOp::Eq)?, 0);
+    assert_eq!(null_memo_count(CmpOp::Is)?, 2);
+    assert_eq!(null_memo_count(CmpOp::Ne)?, 0);
+    assert_eq!(null_memo_count(CmpOp::IsNot)?, 4);
+    let (clause, params) =
+        song_rep.filter_q_config(&Filter::Cmp("memo".to_string(), CmpOp::IsNot, v_txt("60s")))?;
+    assert_eq!(clause, "memo IS NOT ?");
+    assert_eq!(song_rep.count(&conn, None, Some((&clause, &params)))?, 4);
+    assert_eq!(
+        crud::sql::distinct_from("memo", &v_txt("60s"), Some(song_rep.get_defaults()))?,
+        (clause, params)
+    );
+    assert_eq!(
+        crud::sql::not_distinct_from("memo", &types::Value::Null, Some(song_rep.get_defaults()))?,
+        song_rep.filter_q_config(&Filter::Cmp(
+            "memo".to_string(),
+            CmpOp::Is,
+            types::Value::Null
+        ))?
+    );
+
+    let unknown_col_err = song_rep
+        .filter_q_config(&Filter::Cmp("genre".to_string(), CmpOp::Ne, v_txt("pop")))
+        .err()
+        .unwrap();
+    assert_snapshot!(unknown_col_err.to_string());
+
+    let empty_group_err = crud::sql::filter_q_config(&Filter::And(vec![Filter::Or(vec![])]), None)
+        .err()
+        .unwrap();
+    assert_snapshot!(empty_group_err.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_exists() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+
+    assert!(song_rep.exists(&conn, None)?);
+    assert!(!crud::total::t_exists(&conn, "log", None)?);
+    assert!(song_rep.exists(&conn, Some(("name = ?", &[v_txt("We Go!")])))?);
+    assert!(!song_rep.exists(&conn, Some(("name = ?", &[v_txt("We Stop!")])))?);
+
+    assert!(song_rep.exists_by_pk(&conn, &[v_int(1)], None)?);
+    assert!(song_rep.exists_by_pk(&conn, &[v_int(-1), v_int(6)], None)?);
+    assert!(!song_rep.exists_by_pk(&conn, &[v_int(-1)], None)?);
+    assert!(!song_rep.exists_by_pk(&conn, &[v_int(1)], Some(("memo = ?", &[v_txt("90s")])))?);
+
+    Ok(())
+}
+
+#[test]
+fn test_read_by_many_pks() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT)", [])?;
+    for id in 1..=1500 {
+        conn.execute(
+            "INSERT INTO test (id, name) VALUES (?, ?)",
+            (id, format!("test{}", id)),
+        )?;
+    }
+    let pk_values = (1..=1500).map(v_int).collect::<Vec<_>>();
+
+    let records = crud::fetch::f_by_pk(&conn, "test", ("id", &pk_values), None, None)?;
+    assert_eq!(records.len(), 1500);
+
+    let mut repeated_pk_values = pk_values.clone();
+    repeated_pk_values.extend((1..=10).map(v_int));
+    let records = crud::fetch::f_by_pk_in_chunks(
+        &conn,
+        "test",
+        ("id", &repeated_pk_values),
+        Some(("name != ?", &[v_txt("test7")])),
+        Some(&["id"]),
+        100,
+    )?;
+    assert_eq!(records.len(), 1499);
+
+    let err = crud::fetch::f_by_pk_in_chunks(&conn, "test", ("id", &pk_values), None, None, 0)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The chunk size of primary key values must be positive"
+    );
+
+    let where_params = (1..=600)
+        .map(|i| v_txt(&format!("test{}", i)))
+        .collect::<Vec<_>>();
+    let where_clause = format!("name NOT IN ({})", vec!["?"; where_params.len()].join(", "));
+    let where_q_config = Some((where_clause.as_str(), where_params.as_slice()));
+    let err = crud::fetch::f_by_pk_in_chunks(
+        &conn,
+        "test",
+        ("id", &pk_values),
+        where_q_config,
+        None,
+        500,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: test) The chunk size 500 and the other 600 parameters exceed the limit of 999 bound parameters"
+    );
+    let records = crud::fetch::f_by_pk(&conn, "test", ("id", &pk_values), where_q_config, None)?;
+    assert_eq!(records.len(), 900);
+    let records =
+        crud::fetch::f_by_pk_cached(&conn, "test", ("id", &pk_values), where_q_config, None)?;
+    assert_eq!(records.len(), 900);
+
+    let count = crud::total::t_by_pk(&conn, "test", "id", &repeated_pk_values, None, None)?;
+    assert_eq!(count, 1500);
+    let count = crud::total::t_by_pk(&conn, "test", "id", &pk_values, None, where_q_config)?;
+    assert_eq!(count, 900);
+    conn.execute("UPDATE test SET name = 'shared' WHERE id > 1000", [])?;
+    conn.execute("UPDATE test SET name = NULL WHERE id = 1", [])?;
+    let count = crud::total::t_by_pk(&conn, "test", "id", &repeated_pk_values, Some("name"), None)?;
+    assert_eq!(count, 1000);
+    let count = crud::total::t_by_pk(&conn, "test", "id", &pk_values[..10], Some("name"), None)?;
+    assert_eq!(count, 9);
+
+    assert!(crud::total::t_exists_by_pk(
+        &conn,
+        "test",
+        "id",
+        &pk_values,
+        Some(("id = ?", &[v_int(1500)]))
+    )?);
+    let input = HashMap::from([("name".to_string(), v_txt("renamed"))]);
+    let updated = crud::update::u_by_pk(&conn, "test", "id", &pk_values, &input, None, None)?;
+    assert_eq!(updated, 1500);
+    let deleted = crud::del::d_by_pk(&conn, "test", "id", &pk_values[1..], None)?;
+    assert_eq!(deleted, 1499);
+    assert_eq!(crud::total::t_all(&conn, "test", None, None)?, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_count_distinct_combos() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+
+    conn.execute("UPDATE song SET memo = '90s' WHERE id = 6", [])?;
+    assert_eq!(song_rep.count(&conn, Some("artist_id"), None)?, 5);
+    assert_eq!(
+        song_rep.count_distinct_combos(&conn, &["artist_id", "memo"], None)?,
+        5
+    );
+
+    conn.execute("UPDATE song SET memo = NULL WHERE id IN (2, 3)", [])?;
+    assert_eq!(
+        song_rep.count_distinct_combos(&conn, &["artist_id", "memo"], None)?,
+        5
+    );
+    assert_eq!(song_rep.count(&conn, Some("memo"), None)?, 3);
+    assert_eq!(song_rep.count_distinct_combos(&conn, &["memo"], None)?, 4);
+    assert_eq!(
+        song_rep.count_distinct_combos(
+            &conn,
+            &["artist_id", "memo"],
+            Some(("artist_id > ?", &[v_int(3)]))
+        )?,
+        2
+    );
+
+    let err = song_rep
+        .count_distinct_combos(&conn, &["artist_id", "genre"], None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: song) The column 'genre' does not exist"
+    );
+    let err = crud::total::t_distinct_combos(&conn, "song", &[], None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: song) At least 1 column is required to count distinct combinations"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_read_one() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+
+    let song = song_rep.one_by_pk(&conn, &v_int(6), None)?.unwrap();
+    assert_eq!(song["name"], v_txt("We Go!"));
+    assert!(song_rep.one_by_pk(&conn, &v_int(-1), None)?.is_none());
+    assert!(song_rep
+        .one_by_pk(&conn, &v_int(6), Some(("memo = ?", &[v_txt("90s")])))?
+        .is_none());
+
+    let song = song_rep.one_by_pk_as::<serde_json::Value>(&conn, &v_int(1), None)?;
+    assert_eq!(song.unwrap()["memo"], "30s");
+
+    let song = crud::fetch::f_one(&conn, "song", Some(("memo = ?", &[v_txt("80s")])), None)?;
+    assert_eq!(song.unwrap()["id"], v_int(4));
+
+    let many_err = crud::fetch::f_one(&conn, "song", Some(("memo = ?", &[v_txt("60s")])), None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        many_err.to_string(),
+        "(table: song) At most 1 record is expected, but 2 records are found"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_equality_conditions() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+
+    let (clause, params) =
+        crud::sql::eq_all(&[("artist_id", v_int(5)), ("memo", v_txt("90s"))], None)?;
+    assert_eq!(clause, "(artist_id = ? AND memo = ?)");
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0]["id"], v_int(5));
+
+    let (clause, params) = crud::sql::eq_all(&[("memo", v_txt("60s"))], None)?;
+    let (clause, params) = crud::sql::merge_q_configs(
+        Some((&clause, &params)),
+        Some(("id > ?", &[v_int(2)])),
+        "AND",
+    )?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0]["id"], v_int(3));
+
+    let empty_err = crud::sql::eq_all(&[], None).err().unwrap();
+    assert_snapshot!(empty_err.to_string());
+
+    let columns = HashMap::from([
+        ("artist_id".to_string(), v_int(0)),
+        ("memo".to_string(), v_txt("")),
+    ]);
+    let (clause, params) = crud::sql::eq_all(&[("memo", v_txt("60s"))], Some(&columns))?;
+    let songs = crud::fetch::f_all(&conn, "song", Some((&clause, &params)), (false, None))?;
+    assert_eq!(songs.len(), 2);
+    let unknown_err = crud::sql::eq_all(
+        &[("memo", v_txt("60s")), ("genre", v_txt("pop"))],
+        Some(&columns),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        unknown_err.to_string(),
+        "The column 'genre' cannot be used for filtering"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_keyset_pages() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+    let ids_of = |records: &[HashMap<String, types::Value>]| {
+        records
+            .iter()
+            .map(|record| record["id"].clone())
+            .collect::<Vec<_>>()
+    };
+
+    let (page, cursor) = song_rep.list_after(&conn, ("id", None, false, 4), None)?;
+    assert_eq!(ids_of(&page), [1, 2, 3, 4].map(v_int));
+    assert_eq!(cursor, Some(v_int(4)));
+
+    conn.execute(
+        "INSERT INTO song (id, name, artist_id, memo) VALUES (0, 'Inserted', 1, '')",
+        [],
+    )?;
+    let (page, cursor) = song_rep.list_after(&conn, ("id", cursor.as_ref(), false, 4), None)?;
+    assert_eq!(ids_of(&page), [5, 6].map(v_int));
+    assert_eq!(cursor, None);
+
+    let (page, cursor) = song_rep.list_after(
+        &conn,
+        ("id", Some(&v_int(5)), true, 2),
+        Some(("artist_id != ?", &[v_int(2)])),
+    )?;
+    assert_eq!(ids_of(&page), [4, 3].map(v_int));
+    assert_eq!(cursor, Some(v_int(3)));
+
+    let (page, _) =
+        crud::fetch::f_after(&conn, "song", ("id", None, true, 1), None, Some(&["name"]))?;
+    assert_eq!(page[0].len(), 2);
+    assert_eq!(page[0]["id"], v_int(6));
+
+    let err = song_rep
+        .list_after(&conn, ("genre", None, false, 4), None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: song) The column 'genre' cannot be used for ordering"
+    );
+    let err = song_rep
+        .list_after(&conn, ("id", None, false, 0), None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: song) At least 1 record per page is required for this operation"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_read_with_column_order() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+    let columns_of = |record: &[(String, types::Value)]| {
+        record
+            .iter()
+            .map(|(col, _)| col.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let records = song_rep.list_ordered(&conn, Some(("id = ?", &[v_int(1)])), (false, None))?;
+    assert_eq!(records.len(), 1);
+    assert_eq!(columns_of(&records[0]), ["id", "name", "artist_id", "memo"]);
+    let by_map = song_rep.list(&conn, Some(("id = ?", &[v_int(1)])), (false, None))?;
+    assert_eq!(
+        records[0].iter().cloned().collect::<HashMap<_, _>>(),
+        by_map[0]
+    );
+
+    let records = song_rep.list_ordered(&conn, None, (true, Some(&["memo", "artist_id"])))?;
+    for record in &records {
+        assert_eq!(columns_of(record), ["memo", "artist_id"]);
+    }
+    assert_eq!(
+        records.len() as i64,
+        song_rep.count_distinct_combos(&conn, &["memo", "artist_id"], None)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_read_keyed_by_pk() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    initialize_db(&conn)?;
+    let song_rep = TblRep::new(
+        "song",
+        "id",
+        &[
+            ("id", v_int(0)),
+            ("name", v_txt("")),
+            ("artist_id", v_int(0)),
+            ("memo", v_txt("")),
+        ],
+        &["name"],
+    )?;
+
+    let songs = song_rep.map_by_pk(
+        &conn,
+        &[v_int(2), v_int(5), v_int(6), v_int(9)],
+        Some(("artist_id = ?", &[v_int(5)])),
+    )?;
+    let mut keys = songs.keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, ["5", "6"]);
+    assert_eq!(songs["6"]["name"], v_txt("We Go!"));
+
+    let songs =
+        crud::fetch::f_by_pk_keyed(&conn, "song", ("id", &[v_int(1)]), None, Some(&["memo"]))?;
+    assert_eq!(songs["1"].len(), 2);
+    assert_eq!(songs["1"]["memo"], v_txt("30s"));
+
+    conn.execute_batch(
+        "CREATE TABLE loose (code, name TEXT);
+         INSERT INTO loose (code, name) VALUES (1, 'int'), ('1', 'text');",
+    )?;
+    let err = crud::fetch::f_by_pk_keyed(
+        &conn,
+        "loose",
+        ("code", &[v_int(1), v_txt("1")]),
+        None,
+        None,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "(table: loose) The key '1' of 'code' is shared by more than 1 record"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use jankenstore::setup::{apply_recommended_pragmas, set_busy_timeout};
+
+use rusqlite::Connection;
+use std::time::Duration;
+
+fn pragma_value(conn: &Connection, pragma: &str) -> anyhow::Result<String> {
+    let value = conn.query_row(&format!("PRAGMA {}", pragma), [], |row| {
+        row.get::<_, rusqlite::types::Value>(0)
+    })?;
+    Ok(match value {
+        rusqlite::types::Value::Integer(i) => i.to_string(),
+        rusqlite::types::Value::Text(s) => s,
+        other => format!("{:?}", other),
+    })
+}
+
+#[test]
+fn test_recommended_pragmas() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    apply_recommended_pragmas(&conn)?;
+    assert_eq!(pragma_value(&conn, "journal_mode")?, "memory");
+    assert_eq!(pragma_value(&conn, "busy_timeout")?, "5000");
+    assert_eq!(pragma_value(&conn, "synchronous")?, "1");
+    assert_eq!(pragma_value(&conn, "foreign_keys")?, "1");
+
+    let db_path =
+        std::env::temp_dir().join(format!("jankenstore_test_setup_{}.db", std::process::id()));
+    let conn = Connection::open(&db_path)?;
+    apply_recommended_pragmas(&conn)?;
+    assert_eq!(pragma_value(&conn, "journal_mode")?, "wal");
+    drop(conn);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", db_path.display(), suffix));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_busy_timeout() -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+    set_busy_timeout(&conn, Duration::from_millis(1500))?;
+    assert_eq!(pragma_value(&conn, "busy_timeout")?, "1500");
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use jankenstore::{
+    crud::{del, shift::val::v_int},
+    TblRep,
+};
+
+use anyhow::Result;
+use insta::assert_snapshot;
+use rusqlite::{types, Connection};
+use std::collections::HashMap;
+
+#[test]
+fn test_soft_delete() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, deleted_at TEXT)",
+        [],
+    )?;
+    let mut tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("deleted_at", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    for id in 1..=3 {
+        let input = HashMap::from([
+            ("id".to_string(), types::Value::Integer(id)),
+            (
+                "name".to_string(),
+                types::Value::Text(format!("test{}", id)),
+            ),
+        ]);
+        tbl_rep.insert(&conn, &input, true)?;
+    }
+
+    let deleted = tbl_rep.soft_del_by_pk(&conn, &[1, 2].map(v_int), None)?;
+    assert_eq!(deleted, 2);
+
+    assert!(!tbl_rep.get_with_soft_deleted());
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 1);
+    let alive = tbl_rep.list(&conn, None, (false, None))?;
+    assert_eq!(alive.len(), 1);
+    assert_eq!(alive[0]["id"], v_int(3));
+    assert!(tbl_rep.list_by_pk(&conn, &[v_int(1)], None)?.is_empty());
+    assert!(tbl_rep.one_by_pk(&conn, &v_int(1), None)?.is_none());
+    assert!(!tbl_rep.exists_by_pk(&conn, &[1, 2].map(v_int), None)?);
+    let alive = tbl_rep.list(
+        &conn,
+        Some(("id = ? OR id = ?", &[v_int(1), v_int(3)])),
+        (false, None),
+    )?;
+    assert_eq!(alive.len(), 1);
+    assert_eq!(alive[0]["id"], v_int(3));
+
+    tbl_rep.set_with_soft_deleted(true);
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 3);
+
+    let (alive_clause, alive_params) = del::not_soft_deleted();
+    let alive = tbl_rep.list(&conn, Some((&alive_clause, &alive_params)), (false, None))?;
+    assert_eq!(alive.len(), 1);
+    assert_eq!(alive[0]["id"], v_int(3));
+
+    let deleted_at = tbl_rep.list_by_pk(&conn, &[v_int(1)], None)?[0]["deleted_at"].clone();
+    assert!(matches!(deleted_at, types::Value::Text(ref t) if !t.is_empty()));
+
+    let deleted = tbl_rep.soft_del_by_pk(&conn, &[1, 2, 3].map(v_int), None)?;
+    assert_eq!(deleted, 1);
+
+    let hard_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    let no_col_err = hard_rep
+        .soft_del_by_pk(&conn, &[v_int(1)], None)
+        .err()
+        .unwrap();
+    assert_snapshot!(no_col_err.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_soft_delete_many() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, deleted_at TEXT)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("deleted_at", types::Value::Text("".to_string())),
+        ],
+        &["name"],
+    )?;
+    for id in 1..=1500 {
+        conn.execute(
+            "INSERT INTO test (id, name, deleted_at) VALUES (?, ?, '')",
+            (id, format!("test{}", id)),
+        )?;
+    }
+    let pk_values = (1..=1499).map(v_int).collect::<Vec<_>>();
+    let deleted = tbl_rep.soft_del_by_pk(&conn, &pk_values, None)?;
+    assert_eq!(deleted, 1499);
+    let alive = tbl_rep.list(&conn, None, (false, None))?;
+    assert_eq!(alive.len(), 1);
+    assert_eq!(alive[0]["id"], v_int(1500));
+
+    Ok(())
+}
+
+#[test]
+fn test_soft_delete_column_of_other_type() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, deleted_at INTEGER)",
+        [],
+    )?;
+    let tbl_rep = TblRep::new(
+        "test",
+        "id",
+        &[
+            ("id", types::Value::Integer(0)),
+            ("name", types::Value::Text("".to_string())),
+            ("deleted_at", types::Value::Integer(0)),
+        ],
+        &["name"],
+    )?;
+    for id in 1..=3 {
+        let input = HashMap::from([
+            ("id".to_string(), types::Value::Integer(id)),
+            (
+                "name".to_string(),
+                types::Value::Text(format!("test{}", id)),
+            ),
+        ]);
+        tbl_rep.insert(&conn, &input, true)?;
+    }
+
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 3);
+    assert_eq!(tbl_rep.list(&conn, None, (false, None))?.len(), 3);
+    assert!(tbl_rep.one_by_pk(&conn, &v_int(1), None)?.is_some());
+
+    let wrong_type_err = tbl_rep
+        .soft_del_by_pk(&conn, &[v_int(1)], None)
+        .err()
+        .unwrap();
+    assert_eq!(
+        wrong_type_err.to_string(),
+        "(table: test) Soft deletion requires the default value of 'deleted_at' to be an empty text"
+    );
+    assert_eq!(tbl_rep.count(&conn, None, None)?, 3);
+
+    Ok(())
+}